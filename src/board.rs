@@ -1,26 +1,125 @@
-use crate::{Cell, grid::Grid, utils::unflatten};
-use rand::seq::SliceRandom;
+use crate::{
+    Cell,
+    error::Error,
+    explain::{Explanation, Rule},
+    grid::{Grid, Topology},
+    neighbourhood::Neighbourhood,
+    utils::unflatten,
+};
+use rand::{Rng, SeedableRng, seq::SliceRandom};
+use rand_chacha::ChaCha8Rng;
 use std::collections::{HashMap, HashSet};
 use std::fmt::Display;
+use std::str::FromStr;
+
+/// How much of the board around the first click is kept free of bombs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FirstClick {
+    /// only the clicked cell, which may still reveal a number
+    Cell,
+    /// the clicked cell and its neighbours, so the first click always reveals a 0 (Windows-style)
+    Opening,
+}
+
+// the cells kept free of bombs, using the neighbours `shape` gives the first click
+fn first_click_safe_cells(
+    shape: &Grid<Cell>,
+    first_click: (u16, u16),
+    safety: FirstClick,
+) -> Result<HashSet<(u16, u16)>, Error> {
+    let (x, y) = first_click;
+    shape
+        .get_cell(x.into(), y.into())
+        .ok_or(Error::OutOfBounds {
+            x: x.into(),
+            y: y.into(),
+        })?;
+
+    let mut safe_cells = HashSet::from([first_click]);
+    if safety == FirstClick::Opening {
+        safe_cells.extend(shape.neighbours(x, y));
+    }
+
+    Ok(safe_cells)
+}
+
+/// Source of truth for the contents of hidden cells, asked whenever a cell is revealed.
+pub trait RevealOracle {
+    /// Contents of the hidden cell at (x, y), either a `Cell::Number` or `Cell::Bomb`.
+    fn reveal(&mut self, x: i32, y: i32) -> Result<Cell, Error>;
+
+    /// The full solved grid, for oracles that know it up front.
+    fn solution(&self) -> Option<&Grid<Cell>> {
+        None
+    }
+}
+
+/// A solved grid answers reveals straight from its cells.
+impl RevealOracle for Grid<Cell> {
+    fn reveal(&mut self, x: i32, y: i32) -> Result<Cell, Error> {
+        self.get_cell(x, y).ok_or(Error::OutOfBounds { x, y })
+    }
+
+    fn solution(&self) -> Option<&Grid<Cell>> {
+        Some(self)
+    }
+}
+
+/// Oracle for boards where only the player's view is available, every reveal is an error.
+#[derive(Clone, Copy, Debug)]
+pub struct NoOracle;
+
+impl RevealOracle for NoOracle {
+    fn reveal(&mut self, x: i32, y: i32) -> Result<Cell, Error> {
+        Err(Error::Oracle(format!("no oracle to reveal ({}, {})", x, y)))
+    }
+}
 
 #[derive(Clone)]
-pub struct GameBoard {
-    pub width: u8,
-    pub height: u8,
-    pub solved_grid: Grid<Cell>,
+pub struct GameBoard<O = Grid<Cell>> {
+    pub width: u16,
+    pub height: u16,
+    pub oracle: O,
     pub grid: Grid<Cell>,
     pub flag_adj_grid: Grid<Option<u8>>,
-    num_bombs: u16,
-    placed_flags: u16,
+    // `None` when the board is part of a larger one, where the total says nothing about the cells
+    // shown
+    num_bombs: Option<u32>,
+    placed_flags: u32,
+    // why each deduced cell was flagged or revealed, in the order the deductions were applied
+    explanations: Vec<Explanation>,
 }
 
 impl GameBoard {
-    pub fn new(width: u8, height: u8, num_bombs: u16) -> Result<Self, String> {
-        let num_cells = height as u16 * width as u16;
-        assert!(
-            num_bombs <= num_cells,
-            "num_bombs must be less than or equal to width*height"
-        );
+    pub fn new(width: u16, height: u16, num_bombs: u32) -> Result<Self, Error> {
+        GameBoard::generate(width, height, num_bombs, &mut rand::rng())
+    }
+
+    /// Generates a board that is fully determined by `seed`: the same width, height, num_bombs and
+    /// seed always produce the same solved grid. Bomb positions are shuffled with a ChaCha8 RNG
+    /// (`rand_chacha::ChaCha8Rng::seed_from_u64`), so seeds stay valid across platforms.
+    pub fn new_seeded(width: u16, height: u16, num_bombs: u32, seed: u64) -> Result<Self, Error> {
+        GameBoard::generate(
+            width,
+            height,
+            num_bombs,
+            &mut ChaCha8Rng::seed_from_u64(seed),
+        )
+    }
+
+    fn generate(
+        width: u16,
+        height: u16,
+        num_bombs: u32,
+        rng: &mut impl Rng,
+    ) -> Result<Self, Error> {
+        let num_cells = height as u32 * width as u32;
+        if num_bombs > num_cells {
+            return Err(Error::Generation(format!(
+                "cannot place {} bombs in {} cells",
+                num_bombs, num_cells
+            )));
+        }
 
         // create vec with n Cell::Bomb's with Cell::Number(0) right padded to fill up the desired board size
         let mut solved_board: Vec<Cell> = vec![Cell::Bomb; num_bombs as usize];
@@ -29,8 +128,7 @@ impl GameBoard {
         }
 
         // randomize cell positions
-        let mut rng = rand::rng();
-        solved_board.shuffle(&mut rng);
+        solved_board.shuffle(rng);
 
         // unflatten vec into grid
         let solved_board = Grid::from(unflatten(solved_board, width, height)?, width, height)?;
@@ -38,180 +136,441 @@ impl GameBoard {
         GameBoard::from(solved_board)
     }
 
-    pub fn from(mut solved_grid: Grid<Cell>) -> Result<Self, String> {
-        // populate grid cell with correct Cell::Number
-        for cell in solved_grid.get_iter() {
-            let (x, y) = cell.pos;
+    /// Generates a board the way a real game does, after the first click: the clicked cell (and
+    /// with `FirstClick::Opening` all of its neighbours) is guaranteed to be free of bombs.
+    pub fn new_first_click(
+        width: u16,
+        height: u16,
+        num_bombs: u32,
+        first_click: (u16, u16),
+        safety: FirstClick,
+    ) -> Result<Self, Error> {
+        let shape = Grid::new(width, height, Cell::Unknown);
+        let safe_cells = first_click_safe_cells(&shape, first_click, safety)?;
+        GameBoard::with_safe_cells(&shape, num_bombs, &safe_cells, &mut rand::rng())
+    }
 
-            if cell.val != Cell::Bomb {
-                solved_grid.set_cell(
-                    x.into(),
-                    y.into(),
-                    Cell::Number(solved_grid.adj_bombs(x, y)?.len() as u8),
-                )?;
-            }
+    /// Seeded version of `new_first_click`, see `new_seeded` for the RNG used.
+    pub fn new_first_click_seeded(
+        width: u16,
+        height: u16,
+        num_bombs: u32,
+        first_click: (u16, u16),
+        safety: FirstClick,
+        seed: u64,
+    ) -> Result<Self, Error> {
+        let shape = Grid::new(width, height, Cell::Unknown);
+        GameBoard::new_first_click_like(&shape, num_bombs, first_click, safety, seed)
+    }
+
+    /// `new_first_click_seeded` on a board with the size, topology and neighbourhood of `shape`,
+    /// so the cells kept free of bombs are the first click's neighbours on the board played.
+    pub fn new_first_click_like(
+        shape: &Grid<Cell>,
+        num_bombs: u32,
+        first_click: (u16, u16),
+        safety: FirstClick,
+        seed: u64,
+    ) -> Result<Self, Error> {
+        let safe_cells = first_click_safe_cells(shape, first_click, safety)?;
+        GameBoard::with_safe_cells(
+            shape,
+            num_bombs,
+            &safe_cells,
+            &mut ChaCha8Rng::seed_from_u64(seed),
+        )
+    }
+
+    /// Generates a board shaped like `shape` with bombs placed uniformly at random, but never on
+    /// any of `safe_cells`.
+    fn with_safe_cells(
+        shape: &Grid<Cell>,
+        num_bombs: u32,
+        safe_cells: &HashSet<(u16, u16)>,
+        rng: &mut impl Rng,
+    ) -> Result<Self, Error> {
+        let (width, height) = (shape.width, shape.height);
+        let mut candidates: Vec<(u16, u16)> = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .filter(|pos| !safe_cells.contains(pos))
+            .collect();
+
+        if num_bombs as usize > candidates.len() {
+            return Err(Error::Generation(format!(
+                "cannot place {} bombs in {} cells outside of the safe area",
+                num_bombs,
+                candidates.len()
+            )));
         }
 
-        let num_bombs = solved_grid
-            .get_iter()
-            .filter(|cell| cell.val == Cell::Bomb)
-            .count() as u16;
+        candidates.shuffle(rng);
 
-        let width = solved_grid.width;
-        let height = solved_grid.height;
+        let mut solved_grid = shape.new_like(Cell::Number(0));
+        for &(x, y) in &candidates[..num_bombs as usize] {
+            solved_grid.set_cell(x.into(), y.into(), Cell::Bomb)?;
+        }
 
-        let mut flag_adj_grid = Grid::new(width, height, None);
-        for cell in solved_grid.get_iter() {
-            let (x, y) = cell.pos;
-            match cell.val {
-                Cell::Number(num) => flag_adj_grid.set_cell(x.into(), y.into(), Some(num))?,
-                _ => continue,
+        GameBoard::from(solved_grid)
+    }
+
+    /// Generates a board that can be solved from `first_click` without ever guessing. Boards are
+    /// regenerated (with the first click opening up an area) until the deterministic solver clears
+    /// one, or `max_attempts` is exhausted.
+    pub fn new_no_guess(
+        width: u16,
+        height: u16,
+        num_bombs: u32,
+        first_click: (u16, u16),
+        max_attempts: u32,
+    ) -> Result<Self, Error> {
+        GameBoard::no_guess(
+            &Grid::new(width, height, Cell::Unknown),
+            num_bombs,
+            first_click,
+            max_attempts,
+            &mut rand::rng(),
+        )
+    }
+
+    /// Seeded version of `new_no_guess`, see `new_seeded` for the RNG used. Every attempt draws
+    /// from the same RNG, so the seed picks out the whole sequence of boards tried.
+    pub fn new_no_guess_seeded(
+        width: u16,
+        height: u16,
+        num_bombs: u32,
+        first_click: (u16, u16),
+        max_attempts: u32,
+        seed: u64,
+    ) -> Result<Self, Error> {
+        let shape = Grid::new(width, height, Cell::Unknown);
+        GameBoard::new_no_guess_like(&shape, num_bombs, first_click, max_attempts, seed)
+    }
+
+    /// `new_no_guess_seeded` on a board with the size, topology and neighbourhood of `shape`.
+    pub fn new_no_guess_like(
+        shape: &Grid<Cell>,
+        num_bombs: u32,
+        first_click: (u16, u16),
+        max_attempts: u32,
+        seed: u64,
+    ) -> Result<Self, Error> {
+        GameBoard::no_guess(
+            shape,
+            num_bombs,
+            first_click,
+            max_attempts,
+            &mut ChaCha8Rng::seed_from_u64(seed),
+        )
+    }
+
+    fn no_guess(
+        shape: &Grid<Cell>,
+        num_bombs: u32,
+        first_click: (u16, u16),
+        max_attempts: u32,
+        rng: &mut impl Rng,
+    ) -> Result<Self, Error> {
+        let (x, y) = first_click;
+        let safe_cells = first_click_safe_cells(shape, first_click, FirstClick::Opening)?;
+
+        for _ in 0..max_attempts {
+            let board = GameBoard::with_safe_cells(shape, num_bombs, &safe_cells, rng)?;
+
+            let mut solving = board.clone();
+            solving.reveal(x.into(), y.into())?;
+            // hitting a solver limit just means this board is not a no-guess board
+            match solving.solve() {
+                Ok(true) => return Ok(board),
+                Ok(false) | Err(Error::SolverLimit(_)) => continue,
+                Err(err) => return Err(err),
             }
         }
 
-        Ok(GameBoard {
-            width,
-            height,
-            solved_grid,
-            grid: Grid::new(width, height, Cell::Unknown),
-            flag_adj_grid,
-            num_bombs,
-            placed_flags: 0,
-        })
+        Err(Error::Generation(format!(
+            "no no-guess {}x{} board with {} bombs found in {} attempts",
+            shape.width, shape.height, num_bombs, max_attempts
+        )))
     }
 
-    pub fn from_str(solved_grid_str: &str, player_grid_str: &str) -> Result<Self, String> {
+    pub fn from(mut solved_grid: Grid<Cell>) -> Result<Self, Error> {
+        // populate grid cell with correct Cell::Number
+        for y in 0..solved_grid.height {
+            for x in 0..solved_grid.width {
+                if solved_grid.get(x.into(), y.into()) != Some(&Cell::Bomb) {
+                    let bombs = solved_grid
+                        .neighbours(x, y)
+                        .filter(|&(adj_x, adj_y)| {
+                            solved_grid.get(adj_x.into(), adj_y.into()) == Some(&Cell::Bomb)
+                        })
+                        .count();
+                    solved_grid.set_cell(x.into(), y.into(), Cell::Number(bombs as u8))?;
+                }
+            }
+        }
+
+        let num_bombs = solved_grid
+            .get_iter()
+            .filter(|cell| *cell.val == Cell::Bomb)
+            .count() as u32;
+
+        let grid = solved_grid.new_like(Cell::Unknown);
+        GameBoard::from_view(grid, num_bombs, solved_grid)
+    }
+
+    pub fn from_str(solved_grid_str: &str, player_grid_str: &str) -> Result<Self, Error> {
         let solved_grid = Grid::from_str(solved_grid_str)?;
         let player_grid = Grid::from_str(player_grid_str)?;
 
-        if solved_grid.width != player_grid.width {
-            return Err("solved_grid.width != player_grid.width".to_string());
-        } else if solved_grid.height != player_grid.height {
-            return Err("solved_grid.height != player_grid.height".to_string());
+        if solved_grid.width != player_grid.width || solved_grid.height != player_grid.height {
+            return Err(Error::InconsistentBoard(format!(
+                "solved grid is {}x{} but player grid is {}x{}",
+                solved_grid.width, solved_grid.height, player_grid.width, player_grid.height
+            )));
         }
 
         let num_bombs = solved_grid
             .get_iter()
-            .filter(|c| c.val == Cell::Bomb)
-            .count() as u16;
+            .filter(|c| *c.val == Cell::Bomb)
+            .count() as u32;
 
-        let placed_flags: Vec<(u8, u8)> = player_grid
-            .get_iter()
-            .filter(|c| c.val == Cell::Flag)
-            .map(|c| c.pos)
-            .collect();
+        GameBoard::from_view(player_grid, num_bombs, solved_grid)
+    }
+}
+
+impl<O: RevealOracle> GameBoard<O> {
+    /// Builds a board from what the player can see, with `oracle` answering any further reveals.
+    pub fn from_view(grid: Grid<Cell>, num_bombs: u32, oracle: O) -> Result<Self, Error> {
+        GameBoard::build(grid, Some(num_bombs), oracle)
+    }
+
+    /// Builds a board from part of a larger board, whose total bomb count is unknown. The solver
+    /// then only uses the numbers, never the remaining bomb count.
+    pub fn from_partial_view(grid: Grid<Cell>, oracle: O) -> Result<Self, Error> {
+        GameBoard::build(grid, None, oracle)
+    }
 
-        let mut flag_adj_grid = Grid::new(solved_grid.width, solved_grid.height, None);
-        for cell in solved_grid.get_iter() {
+    fn build(grid: Grid<Cell>, num_bombs: Option<u32>, oracle: O) -> Result<Self, Error> {
+        // hex rows only line up across the join of a torus when there is an even number of them
+        if grid.neighbourhood == Neighbourhood::Hex
+            && grid.topology == Topology::Torus
+            && grid.height % 2 == 1
+        {
+            return Err(Error::InconsistentBoard(format!(
+                "a hex torus needs an even height, not {}",
+                grid.height
+            )));
+        }
+
+        let mut flag_adj_grid = grid.new_like(None);
+        let mut placed_flags = 0;
+
+        for cell in grid.get_iter() {
             let (x, y) = cell.pos;
             match cell.val {
-                Cell::Number(num) => flag_adj_grid.set_cell(x.into(), y.into(), Some(num))?,
+                Cell::Number(num) => {
+                    let remaining = num.checked_sub(grid.adj_flags(x, y)?.len() as u8).ok_or(
+                        Error::InconsistentBoard(format!(
+                            "{} at ({}, {}) has too many adjacent flags",
+                            num, x, y
+                        )),
+                    )?;
+                    flag_adj_grid.set_cell(x.into(), y.into(), Some(remaining))?;
+                }
+                Cell::Flag => placed_flags += 1,
                 _ => continue,
             }
         }
 
-        let mut board = GameBoard {
-            width: solved_grid.width,
-            height: solved_grid.height,
-            solved_grid,
-            grid: player_grid,
+        Ok(GameBoard {
+            width: grid.width,
+            height: grid.height,
+            oracle,
+            grid,
             flag_adj_grid,
             num_bombs,
-            placed_flags: placed_flags.len() as u16,
-        };
+            placed_flags,
+            explanations: Vec::new(),
+        })
+    }
 
-        for (x, y) in placed_flags {
-            board.place_flag(x, y).unwrap();
-        }
+    /// The total number of bombs, `None` for a board built with `from_partial_view`.
+    pub fn num_bombs(&self) -> Option<u32> {
+        self.num_bombs
+    }
 
-        Ok(board)
+    /// Number of bombs not yet accounted for by a flag, or `None` if the total is unknown or more
+    /// flags have been placed than there are bombs.
+    pub fn remaining_bombs(&self) -> Option<u32> {
+        self.num_bombs?.checked_sub(self.placed_flags)
     }
 
-    pub fn is_solved(&self) -> Result<bool, String> {
-        for cell in self.solved_grid.get_iter() {
-            let (x, y) = cell.pos;
+    /// Why each cell deduced so far was flagged or revealed, oldest first.
+    pub fn explanations(&self) -> &[Explanation] {
+        &self.explanations
+    }
 
-            let solved_elem = cell.val;
-            if let Some(player_elem) = self.grid.get_cell(x as i16, y as i16) {
-                if let Cell::Number(_) = solved_elem {
-                    if solved_elem == player_elem {
-                        continue;
-                    } else {
-                        return Ok(false);
-                    }
-                } else if solved_elem == Cell::Bomb {
-                    if player_elem == Cell::Unknown || player_elem == Cell::Flag {
-                        continue;
-                    } else {
-                        return Ok(false);
-                    }
+    /// Removes and returns the explanations recorded since the last call.
+    pub fn take_explanations(&mut self) -> Vec<Explanation> {
+        std::mem::take(&mut self.explanations)
+    }
+
+    pub(crate) fn explain(&mut self, explanations: impl IntoIterator<Item = Explanation>) {
+        self.explanations.extend(explanations);
+    }
+
+    /// Whether every safe cell has been revealed without revealing a bomb. Without a total bomb
+    /// count every hidden cell has to be flagged.
+    pub fn is_solved(&self) -> Result<bool, Error> {
+        let mut hidden = 0;
+        let mut unknown = 0;
+        for cell in self.grid.get_iter() {
+            match cell.val {
+                Cell::Bomb => return Ok(false),
+                Cell::Unknown => {
+                    hidden += 1;
+                    unknown += 1;
                 }
+                Cell::Flag => hidden += 1,
+                _ => (),
             }
         }
-        Ok(true)
+
+        Ok(match self.num_bombs {
+            Some(num_bombs) => hidden == num_bombs as usize,
+            None => unknown == 0,
+        })
     }
 
-    pub fn flood_fill(&mut self, x: i16, y: i16) -> Result<(), String> {
+    /// Like `reveal`, but quietly ignores cells that are off the board or already shown. A bomb
+    /// is still left on the player grid, where `is_solved` sees the game was lost.
+    pub fn flood_fill(&mut self, x: i32, y: i32) -> Result<(), Error> {
         match self.grid.get_cell(x, y) {
             None => return Ok(()),
             Some(Cell::Unknown) => (),
             _ => return Ok(()),
         }
 
-        if let Some(cell) = self.solved_grid.get_cell(x, y) {
-            if cell == Cell::Bomb {
-                return Ok(());
-            }
-            // set player cell as solved cell value
-            self.grid.set_cell(x, y, cell)?;
-            // if solved cell value not zero, stop floodfill
-            // ie. continue floodfill only if cell is zero
-            if cell != Cell::Number(0) {
-                return Ok(());
+        let cell = self.oracle.reveal(x, y)?;
+        if cell == Cell::Bomb {
+            return self.grid.set_cell(x, y, Cell::Bomb);
+        }
+
+        self.show(x, y, cell)
+    }
+
+    // sets a revealed cell on the player grid, continuing the flood fill from zeros
+    fn show(&mut self, x: i32, y: i32, cell: Cell) -> Result<(), Error> {
+        // zeros the flood fill still has to continue from. kept on the heap instead of recursing,
+        // as an opening on a large board can be far deeper than the stack
+        let mut zeros = Vec::new();
+
+        self.set_revealed(x, y, cell)?;
+        if cell == Cell::Number(0) {
+            zeros.push((x, y));
+        }
+
+        while let Some((x, y)) = zeros.pop() {
+            for (adj_x, adj_y) in self.grid.neighbours(x as u16, y as u16) {
+                let (adj_x, adj_y) = (adj_x.into(), adj_y.into());
+                if self.grid.get(adj_x, adj_y) != Some(&Cell::Unknown) {
+                    continue;
+                }
+
+                let cell = self.oracle.reveal(adj_x, adj_y)?;
+                if cell == Cell::Bomb {
+                    continue;
+                }
+
+                self.set_revealed(adj_x, adj_y, cell)?;
+                if cell == Cell::Number(0) {
+                    zeros.push((adj_x, adj_y));
+                }
             }
         }
 
-        self.flood_fill_all_adj(x, y)
+        Ok(())
     }
 
-    pub fn flood_fill_all_adj(&mut self, x: i16, y: i16) -> Result<(), String> {
-        let deltas: [(i16, i16); 8] = [
-            (-1, 1),
-            (0, 1),
-            (1, 1),
-            (-1, 0),
-            (1, 0),
-            (-1, -1),
-            (0, -1),
-            (1, -1),
-        ];
+    fn set_revealed(&mut self, x: i32, y: i32, cell: Cell) -> Result<(), Error> {
+        // set player cell as revealed cell value
+        self.grid.set_cell(x, y, cell)?;
+
+        if let Cell::Number(num) = cell {
+            let remaining = num
+                .checked_sub(self.grid.adj_flags(x as u16, y as u16)?.len() as u8)
+                .ok_or(Error::InconsistentBoard(format!(
+                    "{} at ({}, {}) has too many adjacent flags",
+                    num, x, y
+                )))?;
+            self.flag_adj_grid.set_cell(x, y, Some(remaining))?;
+        }
+
+        Ok(())
+    }
+
+    /// Reveals the cell at (x, y), returning `false` if it was a bomb.
+    pub fn reveal(&mut self, x: i32, y: i32) -> Result<bool, Error> {
+        match self.grid.get_cell(x, y) {
+            None => return Err(Error::OutOfBounds { x, y }),
+            Some(Cell::Unknown) => (),
+            _ => return Ok(true),
+        }
+
+        let cell = self.oracle.reveal(x, y)?;
+        if cell == Cell::Bomb {
+            self.grid.set_cell(x, y, Cell::Bomb)?;
+            return Ok(false);
+        }
+
+        self.show(x, y, cell)?;
+        Ok(true)
+    }
+
+    pub fn flood_fill_all_adj(&mut self, x: i32, y: i32) -> Result<(), Error> {
+        if self.grid.get(x, y).is_none() {
+            return Err(Error::OutOfBounds { x, y });
+        }
 
-        for d in deltas {
-            self.flood_fill(x + d.0, y + d.1)?;
+        for (adj_x, adj_y) in self.grid.neighbours(x as u16, y as u16) {
+            self.flood_fill(adj_x.into(), adj_y.into())?;
         }
 
         Ok(())
     }
 
-    pub fn chord(&mut self, x: i16, y: i16) -> Result<(), String> {
+    pub fn chord(&mut self, x: i32, y: i32) -> Result<(), Error> {
         let cell = self.grid.get_cell(x, y);
 
-        match cell {
+        let num = match cell {
             Some(Cell::Number(num)) => {
                 if num == 0 {
                     return Ok(()); // return if cell is 0
                 }
-                if self.grid.adj_flags(x as u8, y as u8)?.len() != num as usize {
+                if self.grid.adj_flags(x as u16, y as u16)?.len() != num as usize {
                     return Ok(()); // not enough adjacent flags to chord
                 }
+                num
             }
             _ => return Ok(()), // return if cell is not a number
-        }
+        };
+
+        let unknown =
+            self.grid
+                .adj_cells(x as u16, y as u16, Some(HashSet::from([Cell::Unknown])))?;
+        self.explain(unknown.into_iter().map(|cell| Explanation {
+            cell,
+            bomb: false,
+            rule: Rule::AllSafe {
+                number: (x as u16, y as u16),
+                value: num,
+                flags: num.into(),
+            },
+        }));
 
         self.flood_fill_all_adj(x, y)
     }
 
-    pub fn chord_all(&mut self) -> Result<(), String> {
+    pub fn chord_all(&mut self) -> Result<(), Error> {
         for y in 0..self.height {
             for x in 0..self.width {
                 self.chord(x.into(), y.into())?;
@@ -221,51 +580,71 @@ impl GameBoard {
         Ok(())
     }
 
-    pub fn place_flag(&mut self, x: u8, y: u8) -> Result<(), String> {
+    pub fn place_flag(&mut self, x: u16, y: u16) -> Result<(), Error> {
         self.grid.set_cell(x.into(), y.into(), Cell::Flag)?;
-        for (x, y) in self.grid.adj_cells(x, y, None)? {
+        self.placed_flags += 1;
+        for (x, y) in self.grid.neighbours(x, y) {
             self.flag_adj_grid.decr(x, y)?;
         }
 
         Ok(())
     }
 
-    pub fn place_flags(&mut self, x: u8, y: u8) -> Result<(), String> {
-        let cell = self.grid.get_cell(x.into(), y.into());
+    pub fn place_flags(&mut self, x: u16, y: u16) -> Result<(), Error> {
+        // only numbers with bombs left to place, checked first as this runs for every cell
+        let Some(Cell::Number(num)) = self.grid.get_cell(x.into(), y.into()) else {
+            return Ok(());
+        };
+        if self.flag_adj_grid.get_cell(x.into(), y.into()) == Some(Some(0)) {
+            return Ok(());
+        }
+
         let adj = self
             .grid
             .adj_cells(x, y, Some(HashSet::from([Cell::Unknown, Cell::Flag])))?;
 
-        if let Some(Cell::Number(num)) = cell {
-            if adj.len() == num as usize {
-                for (x, y) in adj {
-                    match self.grid.get_cell(x.into(), y.into()) {
-                        Some(Cell::Flag) => (),
-                        _ => self.place_flag(x, y)?,
-                    }
-                }
+        if adj.len() == num as usize {
+            let unknown: Vec<(u16, u16)> = adj
+                .into_iter()
+                .filter(|&(x, y)| self.grid.get_cell(x.into(), y.into()) != Some(Cell::Flag))
+                .collect();
+            let flags = num as usize - unknown.len();
+
+            for &(bomb_x, bomb_y) in &unknown {
+                self.place_flag(bomb_x, bomb_y)?;
+                self.explain([Explanation {
+                    cell: (bomb_x, bomb_y),
+                    bomb: true,
+                    rule: Rule::AllBombs {
+                        number: (x, y),
+                        value: num,
+                        flags,
+                        unknown: unknown.len(),
+                    },
+                }]);
             }
         }
 
         Ok(())
     }
 
-    pub fn place_all_flags(&mut self) -> Result<(), String> {
-        for cell in self.grid.get_iter() {
-            let (x, y) = cell.pos;
-            self.place_flags(x, y)?;
+    pub fn place_all_flags(&mut self) -> Result<(), Error> {
+        for y in 0..self.grid.height {
+            for x in 0..self.grid.width {
+                self.place_flags(x, y)?;
+            }
         }
 
         Ok(())
     }
 }
 
-impl Display for GameBoard {
+impl<O: RevealOracle> Display for GameBoard<O> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let flag_pos: Vec<(u8, u8)> = self
+        let flag_pos: Vec<(u16, u16)> = self
             .grid
             .get_iter()
-            .filter(|cell| cell.val == Cell::Flag)
+            .filter(|cell| *cell.val == Cell::Flag)
             .map(|cell| cell.pos)
             .collect();
         let mut flag_pos_map = HashMap::new();
@@ -273,10 +652,13 @@ impl Display for GameBoard {
             flag_pos_map.insert(pos, (100, 50, 50));
         }
 
+        if let Some(solved_grid) = self.oracle.solution() {
+            f.write_str(format!("solved:\n{}\n", solved_grid).as_str())?;
+        }
+
         f.write_str(
             format!(
-                "solved:\n{}\nflag adj:\n{}\ncurrent:\n{}",
-                self.solved_grid,
+                "flag adj:\n{}\ncurrent:\n{}",
                 self.flag_adj_grid.to_string(Some(flag_pos_map)),
                 self.grid
             )
@@ -284,3 +666,349 @@ impl Display for GameBoard {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bomb_positions(board: &GameBoard) -> Vec<(u16, u16)> {
+        board
+            .oracle
+            .get_iter()
+            .filter(|cell| *cell.val == Cell::Bomb)
+            .map(|cell| cell.pos)
+            .collect()
+    }
+
+    #[test]
+    fn new_seeded_1() {
+        let board = GameBoard::new_seeded(16, 16, 40, 1234).unwrap();
+        let other = GameBoard::new_seeded(16, 16, 40, 1234).unwrap();
+
+        assert_eq!(board.oracle, other.oracle);
+        assert_eq!(board.num_bombs(), Some(40));
+    }
+
+    #[test]
+    fn new_seeded_2() {
+        let board = GameBoard::new_seeded(16, 16, 40, 1234).unwrap();
+        let other = GameBoard::new_seeded(16, 16, 40, 1235).unwrap();
+
+        assert_ne!(board.oracle, other.oracle);
+    }
+
+    #[test]
+    fn new_seeded_3() {
+        // pins the RNG algorithm, if this changes previously shared seeds no longer replay
+        let board = GameBoard::new_seeded(5, 5, 4, 42).unwrap();
+
+        assert_eq!(bomb_positions(&board), vec![(0, 1), (3, 2), (1, 3), (1, 4)]);
+    }
+
+    #[test]
+    fn new_first_click_1() {
+        // every other cell is a bomb, so the first click must reveal an 8
+        let board = GameBoard::new_first_click(3, 3, 8, (1, 1), FirstClick::Cell).unwrap();
+
+        assert_eq!(board.oracle.get_cell(1, 1), Some(Cell::Number(8)));
+    }
+
+    #[test]
+    fn new_first_click_2() {
+        for seed in 0..20 {
+            let board =
+                GameBoard::new_first_click_seeded(9, 9, 30, (0, 4), FirstClick::Opening, seed)
+                    .unwrap();
+
+            assert_eq!(board.oracle.get_cell(0, 4), Some(Cell::Number(0)));
+            assert_eq!(bomb_positions(&board).len(), 30);
+        }
+    }
+
+    #[test]
+    fn new_first_click_3() {
+        let board = GameBoard::new_first_click(3, 3, 1, (1, 1), FirstClick::Opening);
+        assert!(board.is_err());
+
+        let board = GameBoard::new_first_click(3, 3, 1, (3, 1), FirstClick::Cell);
+        assert!(board.is_err());
+    }
+
+    #[test]
+    fn flood_fill_1() {
+        let mut board = GameBoard::from_str(
+            "B  1  0
+1  1  0",
+            "?  ?  ?
+?  ?  ?",
+        )
+        .unwrap();
+
+        // the bomb stays on the board, so revealing every safe cell afterwards doesn't win
+        board.flood_fill(0, 1).unwrap();
+        assert_eq!(board.grid.get_cell(0, 1), Some(Cell::Bomb));
+
+        board.flood_fill(2, 0).unwrap();
+        board.flood_fill(0, 0).unwrap();
+        assert!(!board.is_solved().unwrap());
+    }
+
+    #[test]
+    fn new_first_click_4() {
+        // the corner's neighbours wrap around a torus and a knight jumps to them, so they need to
+        // be kept clear on the board actually played
+        let shapes = [
+            Grid::new(9, 9, Cell::Unknown).with_topology(Topology::Torus),
+            Grid::new(9, 9, Cell::Unknown).with_neighbourhood(Neighbourhood::Knight),
+        ];
+        for shape in shapes {
+            for seed in 0..20 {
+                let board =
+                    GameBoard::new_first_click_like(&shape, 50, (0, 0), FirstClick::Opening, seed)
+                        .unwrap();
+
+                assert_eq!(board.grid.topology, shape.topology);
+                assert_eq!(board.grid.neighbourhood, shape.neighbourhood);
+                assert_eq!(board.oracle.get_cell(0, 0), Some(Cell::Number(0)));
+            }
+        }
+    }
+
+    #[test]
+    fn new_no_guess_1() {
+        let board = GameBoard::new_no_guess(9, 9, 10, (4, 4), 1000).unwrap();
+
+        assert_eq!(board.num_bombs(), Some(10));
+        assert_eq!(board.oracle.get_cell(4, 4), Some(Cell::Number(0)));
+
+        let mut solving = board.clone();
+        solving.reveal(4, 4).unwrap();
+        assert!(solving.solve().unwrap());
+    }
+
+    #[test]
+    fn new_no_guess_2() {
+        // every cell outside of the first click's neighbourhood is a bomb, which leaves no room
+        let board = GameBoard::new_no_guess(5, 5, 17, (2, 2), 10);
+        assert!(board.is_err());
+    }
+
+    #[test]
+    fn new_no_guess_3() {
+        let board = GameBoard::new_no_guess_seeded(9, 9, 10, (4, 4), 1000, 1234).unwrap();
+        let other = GameBoard::new_no_guess_seeded(9, 9, 10, (4, 4), 1000, 1234).unwrap();
+
+        assert_eq!(board.oracle, other.oracle);
+    }
+
+    #[test]
+    fn topology_1() {
+        let solved = Grid::from_str(
+            "B  0  0  0  0
+0  0  0  0  0
+0  0  0  0  0
+0  0  0  0  0
+0  0  0  0  0",
+        )
+        .unwrap();
+
+        let board = GameBoard::from(solved.clone()).unwrap();
+        assert_eq!(board.oracle.get_cell(4, 0), Some(Cell::Number(0)));
+
+        // the bomb in the top left corner touches every other corner across the joined edges
+        let mut board = GameBoard::from(solved.with_topology(Topology::Torus)).unwrap();
+        assert_eq!(board.grid.topology, Topology::Torus);
+        for (x, y) in [(4, 4), (0, 0), (4, 0), (1, 0), (4, 3)] {
+            assert_eq!(board.oracle.get_cell(x, y), Some(Cell::Number(1)));
+        }
+
+        assert!(board.reveal(2, 2).unwrap());
+        assert!(board.is_solved().unwrap());
+    }
+
+    #[test]
+    fn neighbourhood_1() {
+        let solved = Grid::from_str(
+            "0  0  0  0  0
+0  0  0  0  0
+0  0  B  0  0
+0  0  0  0  0
+0  0  0  0  0",
+        )
+        .unwrap();
+
+        let board = GameBoard::from(solved.with_neighbourhood(Neighbourhood::Knight)).unwrap();
+        for y in 0..5 {
+            for x in 0..5 {
+                let knight_move = (x - 2i32).abs() * (y - 2i32).abs() == 2;
+                let expected = if (x, y) == (2, 2) {
+                    Cell::Bomb
+                } else {
+                    Cell::Number(knight_move.into())
+                };
+                assert_eq!(
+                    board.oracle.get_cell(x, y),
+                    Some(expected),
+                    "({}, {})",
+                    x,
+                    y
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn shapes_1() {
+        let custom =
+            Neighbourhood::custom(&[(0, 2), (0, -2), (-1, 0), (1, 0), (1, 1), (-1, -1)]).unwrap();
+        let shapes = [
+            (Topology::Torus, Neighbourhood::Moore, 40),
+            (Topology::Cylinder, Neighbourhood::Moore, 40),
+            (Topology::Bounded, Neighbourhood::VonNeumann, 30),
+            (Topology::Bounded, Neighbourhood::Knight, 30),
+            (Topology::Bounded, Neighbourhood::Radius2, 15),
+            (Topology::Bounded, Neighbourhood::Hex, 30),
+            (Topology::Torus, Neighbourhood::Hex, 30),
+            (Topology::Bounded, custom, 30),
+        ];
+
+        for (topology, neighbourhood, num_bombs) in shapes {
+            let shape = Grid::new(16, 16, Cell::Unknown)
+                .with_topology(topology)
+                .with_neighbourhood(neighbourhood.clone());
+
+            let mut solved_boards = 0;
+            for seed in 0..10 {
+                let board = GameBoard::new_first_click_like(
+                    &shape,
+                    num_bombs,
+                    (8, 8),
+                    FirstClick::Opening,
+                    seed,
+                )
+                .unwrap();
+
+                let mut solving = board.clone();
+                assert!(solving.reveal(8, 8).unwrap());
+                if solving.solve().unwrap() {
+                    solved_boards += 1;
+                }
+
+                // every deduction, including those across joined edges, agrees with the solution
+                for cell in solving.grid.get_iter() {
+                    let (x, y) = cell.pos;
+                    let solution = board.oracle.get_cell(x.into(), y.into()).unwrap();
+                    match cell.val {
+                        Cell::Unknown => (),
+                        Cell::Flag => assert_eq!(solution, Cell::Bomb),
+                        val => assert_eq!(
+                            *val, solution,
+                            "{:?} {:?} seed {}",
+                            topology, neighbourhood, seed
+                        ),
+                    }
+                }
+            }
+
+            assert!(solved_boards > 0, "{:?} {:?}", topology, neighbourhood);
+        }
+    }
+
+    #[test]
+    fn hex_1() {
+        let solved = Grid::from_hex_str(
+            " 0  0  0  0  0
+0  B  0  0  0
+ 0  0  0  0  B
+0  0  0  0  0",
+        )
+        .unwrap();
+        assert_eq!(solved.neighbourhood, Neighbourhood::Hex);
+
+        let mut board = GameBoard::from(solved.clone()).unwrap();
+        for (x, y) in [
+            (0, 3),
+            (1, 3),
+            (0, 2),
+            (2, 2),
+            (0, 1),
+            (1, 1),
+            (4, 2),
+            (3, 1),
+            (4, 0),
+        ] {
+            assert_eq!(board.oracle.get_cell(x, y), Some(Cell::Number(1)));
+        }
+        // both are next to the bomb at (1, 2) on a square grid, but not on a hex grid
+        assert_eq!(board.oracle.get_cell(2, 1), Some(Cell::Number(0)));
+        assert_eq!(board.oracle.get_cell(2, 3), Some(Cell::Number(0)));
+
+        assert!(board.reveal(2, 0).unwrap());
+        assert!(board.solve().unwrap());
+        assert!(board.is_solved().unwrap());
+
+        let board = GameBoard::from(solved.with_topology(Topology::Torus));
+        assert!(board.is_ok());
+    }
+
+    #[test]
+    fn hex_2() {
+        let solved = Grid::new(5, 3, Cell::Number(0))
+            .with_neighbourhood(Neighbourhood::Hex)
+            .with_topology(Topology::Torus);
+        assert!(GameBoard::from(solved).is_err());
+    }
+
+    #[test]
+    fn large_board_1() {
+        // wider and taller than 255 with more than 65535 bombs
+        let board =
+            GameBoard::new_first_click_seeded(300, 400, 70_000, (299, 399), FirstClick::Cell, 3)
+                .unwrap();
+        assert_eq!(board.num_bombs(), Some(70_000));
+        assert_eq!(bomb_positions(&board).len(), 70_000);
+
+        let mut solving = board.clone();
+        assert!(solving.reveal(299, 399).unwrap());
+        assert_eq!(
+            solving.grid.get_cell(299, 399),
+            board.oracle.get_cell(299, 399)
+        );
+
+        let rendered = solving.grid.to_string(None);
+        assert!(rendered.contains(" | 399\n"));
+        assert_eq!(rendered.lines().count(), 400 + 1 + 3);
+    }
+
+    #[test]
+    fn large_board_2() {
+        // a single opening of 40000 cells, which used to overflow the stack while flood filling
+        let mut board =
+            GameBoard::new_first_click_seeded(200, 200, 1, (0, 0), FirstClick::Opening, 1).unwrap();
+
+        assert!(board.reveal(0, 0).unwrap());
+        assert!(board.solve().unwrap());
+    }
+
+    #[test]
+    #[ignore]
+    fn large_board_3() {
+        // this takes ~12s in release build, run with
+        // `cargo test --release large_board_3 -- --ignored --nocapture`
+        time_test!();
+
+        let mut board = GameBoard::new_first_click_seeded(
+            10_000,
+            10_000,
+            15_000_000,
+            (5000, 5000),
+            FirstClick::Opening,
+            1,
+        )
+        .unwrap();
+
+        assert!(board.reveal(5000, 5000).unwrap());
+        assert!(board.simple_solve_step().unwrap());
+        assert_eq!(board.grid.get_cell(5000, 5000), Some(Cell::Number(0)));
+    }
+}
@@ -0,0 +1,562 @@
+use std::collections::{HashMap, HashSet};
+
+use rand::{SeedableRng, seq::SliceRandom};
+use rand_chacha::ChaCha8Rng;
+
+use crate::{
+    Cell,
+    board::{GameBoard, RevealOracle},
+    error::Error,
+    grid::Grid,
+    neighbourhood::Neighbourhood,
+};
+
+// what a cell of the endless board holds, given where the bombs are. chunked boards only support
+// the Moore neighbourhood, which is also what the plain `Grid` windows of solve_step use
+fn cell_from_bombs(x: i32, y: i32, is_bomb: impl Fn(i32, i32) -> bool) -> Cell {
+    if is_bomb(x, y) {
+        return Cell::Bomb;
+    }
+
+    let bombs = Neighbourhood::Moore
+        .deltas(y)
+        .iter()
+        .filter(|(dx, dy)| is_bomb(x + dx, y + dy))
+        .count();
+    Cell::Number(bombs as u8)
+}
+
+// splitmix64 finaliser, spreads nearby inputs (eg. neighbouring chunk coordinates) across all bits
+fn mix(mut z: u64) -> u64 {
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+/// Bomb layout of an endless board, generated one chunk of `chunk_size` x `chunk_size` cells at a
+/// time. Every chunk holds exactly `bombs_per_chunk` bombs, shuffled in with a `ChaCha8Rng` seeded
+/// from the world seed and the chunk coordinates, so separate processes with the same world seed
+/// place the same bombs. The cells around (0, 0) never hold a bomb so a game can start there.
+/// Numbers always count the 8 surrounding cells, other neighbourhoods are not supported.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ChunkGenerator {
+    seed: u64,
+    chunk_size: u16,
+    bombs_per_chunk: u32,
+}
+
+impl ChunkGenerator {
+    pub fn new(seed: u64, chunk_size: u16, bombs_per_chunk: u32) -> Result<Self, Error> {
+        // the chunk at (0, 0) loses 4 of its cells to the safe area around the origin. with fewer
+        // than 1 bomb in 8 cells the zeros percolate and an opening can go on forever
+        let cells = u32::from(chunk_size) * u32::from(chunk_size);
+        if chunk_size < 3
+            || bombs_per_chunk.saturating_mul(8) < cells
+            || bombs_per_chunk > cells - 4
+        {
+            return Err(Error::Generation(format!(
+                "cannot place {} bombs in every {}x{} chunk",
+                bombs_per_chunk, chunk_size, chunk_size
+            )));
+        }
+
+        Ok(ChunkGenerator {
+            seed,
+            chunk_size,
+            bombs_per_chunk,
+        })
+    }
+
+    pub fn chunk_size(&self) -> u16 {
+        self.chunk_size
+    }
+
+    /// The chunk holding the world cell (x, y), and the position of the cell inside it.
+    pub fn locate(&self, x: i32, y: i32) -> ((i32, i32), (u16, u16)) {
+        let size = i32::from(self.chunk_size);
+
+        (
+            (x.div_euclid(size), y.div_euclid(size)),
+            (x.rem_euclid(size) as u16, y.rem_euclid(size) as u16),
+        )
+    }
+
+    /// Whether each cell of `chunk` is a bomb, row by row from the bottom left of the chunk.
+    pub fn bombs(&self, chunk: (i32, i32)) -> Vec<bool> {
+        let size = usize::from(self.chunk_size);
+        let (origin_x, origin_y) = self.origin(chunk);
+
+        let mut candidates: Vec<usize> = (0..size * size)
+            .filter(|i| {
+                let x = origin_x + (i % size) as i32;
+                let y = origin_y + (i / size) as i32;
+                x.abs() > 1 || y.abs() > 1
+            })
+            .collect();
+        candidates.shuffle(&mut ChaCha8Rng::seed_from_u64(self.chunk_seed(chunk)));
+
+        let mut bombs = vec![false; size * size];
+        for &i in &candidates[..self.bombs_per_chunk as usize] {
+            bombs[i] = true;
+        }
+
+        bombs
+    }
+
+    // world position of the bottom left cell of `chunk`
+    fn origin(&self, (chunk_x, chunk_y): (i32, i32)) -> (i32, i32) {
+        let size = i32::from(self.chunk_size);
+        (chunk_x * size, chunk_y * size)
+    }
+
+    fn chunk_seed(&self, (chunk_x, chunk_y): (i32, i32)) -> u64 {
+        mix(mix(self.seed ^ mix(chunk_x as u32 as u64)) ^ chunk_y as u32 as u64)
+    }
+
+    /// The solved cells of `chunk`. Numbers along its edges count the bombs of the neighbouring
+    /// chunks too, so their layouts are generated as well.
+    pub fn solve_chunk(&self, chunk: (i32, i32)) -> Result<Grid<Cell>, Error> {
+        let size = self.chunk_size;
+        let (origin_x, origin_y) = self.origin(chunk);
+
+        let mut layouts = HashMap::new();
+        for dy in -1..=1 {
+            for dx in -1..=1 {
+                let neighbour = (chunk.0 + dx, chunk.1 + dy);
+                layouts.insert(neighbour, self.bombs(neighbour));
+            }
+        }
+        let is_bomb = |x: i32, y: i32| {
+            let (chunk, (local_x, local_y)) = self.locate(x, y);
+            layouts[&chunk][usize::from(local_y) * usize::from(size) + usize::from(local_x)]
+        };
+
+        let mut solved = Grid::new(size, size, Cell::Number(0));
+        for local_y in 0..size {
+            for local_x in 0..size {
+                let (x, y) = (origin_x + i32::from(local_x), origin_y + i32::from(local_y));
+                solved.set_cell(
+                    local_x.into(),
+                    local_y.into(),
+                    cell_from_bombs(x, y, is_bomb),
+                )?;
+            }
+        }
+
+        Ok(solved)
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Chunk {
+    solved: Grid<Cell>,
+    // what the players can see of the chunk
+    grid: Grid<Cell>,
+}
+
+/// An endless board made of chunks from a `ChunkGenerator`. A chunk is only materialised once it
+/// is touched, by a reveal or flag inside it or a flood fill crossing into it.
+#[derive(Clone, Debug)]
+pub struct ChunkedBoard {
+    pub generator: ChunkGenerator,
+    chunks: HashMap<(i32, i32), Chunk>,
+}
+
+impl ChunkedBoard {
+    pub fn new(generator: ChunkGenerator) -> Self {
+        ChunkedBoard {
+            generator,
+            chunks: HashMap::new(),
+        }
+    }
+
+    /// The coordinates of every materialised chunk, in no particular order.
+    pub fn chunks(&self) -> impl Iterator<Item = (i32, i32)> + '_ {
+        self.chunks.keys().copied()
+    }
+
+    fn chunk_mut(&mut self, chunk: (i32, i32)) -> Result<&mut Chunk, Error> {
+        if !self.chunks.contains_key(&chunk) {
+            let size = self.generator.chunk_size;
+            self.chunks.insert(
+                chunk,
+                Chunk {
+                    solved: self.generator.solve_chunk(chunk)?,
+                    grid: Grid::new(size, size, Cell::Unknown),
+                },
+            );
+        }
+
+        Ok(self.chunks.get_mut(&chunk).unwrap())
+    }
+
+    /// What the players can see at (x, y), `Cell::Unknown` anywhere that has not been materialised.
+    pub fn get_cell(&self, x: i32, y: i32) -> Cell {
+        let (chunk, (local_x, local_y)) = self.generator.locate(x, y);
+
+        self.chunks
+            .get(&chunk)
+            .and_then(|chunk| chunk.grid.get_cell(local_x.into(), local_y.into()))
+            .unwrap_or(Cell::Unknown)
+    }
+
+    fn set_cell(&mut self, x: i32, y: i32, cell: Cell) -> Result<(), Error> {
+        let (chunk, (local_x, local_y)) = self.generator.locate(x, y);
+        self.chunk_mut(chunk)?
+            .grid
+            .set_cell(local_x.into(), local_y.into(), cell)
+    }
+
+    fn solved_cell(&mut self, x: i32, y: i32) -> Result<Cell, Error> {
+        let (chunk, (local_x, local_y)) = self.generator.locate(x, y);
+        self.chunk_mut(chunk)?
+            .solved
+            .reveal(local_x.into(), local_y.into())
+    }
+
+    /// Reveals the cell at (x, y), flood filling from zeros into as many chunks as it takes.
+    /// Returns `false` if it was a bomb.
+    pub fn reveal(&mut self, x: i32, y: i32) -> Result<bool, Error> {
+        if self.get_cell(x, y) != Cell::Unknown {
+            return Ok(true);
+        }
+
+        let cell = self.solved_cell(x, y)?;
+        self.set_cell(x, y, cell)?;
+        if cell == Cell::Bomb {
+            return Ok(false);
+        }
+
+        if cell == Cell::Number(0) {
+            self.flood_fill(vec![(x, y)])?;
+        }
+
+        Ok(true)
+    }
+
+    // reveals the neighbours of every revealed zero in `zeros`, and of the zeros that uncovers
+    fn flood_fill(&mut self, mut zeros: Vec<(i32, i32)>) -> Result<(), Error> {
+        while let Some((x, y)) = zeros.pop() {
+            for (dx, dy) in Neighbourhood::Moore.deltas(y) {
+                let (adj_x, adj_y) = (x + dx, y + dy);
+                if self.get_cell(adj_x, adj_y) != Cell::Unknown {
+                    continue;
+                }
+
+                // next to a zero, so never a bomb
+                let cell = self.solved_cell(adj_x, adj_y)?;
+                self.set_cell(adj_x, adj_y, cell)?;
+                if cell == Cell::Number(0) {
+                    zeros.push((adj_x, adj_y));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn flag(&mut self, x: i32, y: i32) -> Result<(), Error> {
+        if self.get_cell(x, y) == Cell::Unknown {
+            self.set_cell(x, y, Cell::Flag)?;
+        }
+
+        Ok(())
+    }
+
+    /// What the players can see of the `width` x `height` cells with `origin` at the bottom left.
+    pub fn view(&self, origin: (i32, i32), width: u16, height: u16) -> Result<Grid<Cell>, Error> {
+        let mut grid = Grid::new(width, height, Cell::Unknown);
+        for y in 0..height {
+            for x in 0..width {
+                let cell = self.get_cell(origin.0 + i32::from(x), origin.1 + i32::from(y));
+                grid.set_cell(x.into(), y.into(), cell)?;
+            }
+        }
+
+        Ok(grid)
+    }
+
+    /// Runs one round of the deterministic solve steps (as in `GameBoard::solve`) over every
+    /// materialised chunk, returning whether it made progress. Touching chunks are grouped into
+    /// clusters and the steps work on a window over each cluster with a border of one cell, so
+    /// every revealed number has all of its neighbours in the window. They never use a bomb count
+    /// as the board has no total.
+    pub fn solve_step(&mut self) -> Result<bool, Error> {
+        let mut progress = false;
+        for (min_x, min_y, max_x, max_y) in self.clusters() {
+            progress |= self.solve_window(min_x, min_y, max_x, max_y)?;
+        }
+
+        Ok(progress)
+    }
+
+    // the bounds (min x, min y, max x, max y) of each group of materialised chunks that touch,
+    // including diagonally, ordered by their lowest chunk
+    fn clusters(&self) -> Vec<(i32, i32, i32, i32)> {
+        let mut chunks: Vec<(i32, i32)> = self.chunks.keys().copied().collect();
+        chunks.sort();
+
+        let mut seen = HashSet::new();
+        let mut clusters = Vec::new();
+        for &start in &chunks {
+            if !seen.insert(start) {
+                continue;
+            }
+
+            let (mut min_x, mut min_y, mut max_x, mut max_y) = (start.0, start.1, start.0, start.1);
+            let mut stack = vec![start];
+            while let Some((x, y)) = stack.pop() {
+                (min_x, min_y) = (min_x.min(x), min_y.min(y));
+                (max_x, max_y) = (max_x.max(x), max_y.max(y));
+
+                for (dx, dy) in Neighbourhood::Moore.deltas(y) {
+                    let neighbour = (x + dx, y + dy);
+                    if self.chunks.contains_key(&neighbour) && seen.insert(neighbour) {
+                        stack.push(neighbour);
+                    }
+                }
+            }
+            clusters.push((min_x, min_y, max_x, max_y));
+        }
+
+        clusters
+    }
+
+    // one round of solve steps on the window over the chunks from (min_x, min_y) to (max_x, max_y)
+    fn solve_window(
+        &mut self,
+        min_x: i32,
+        min_y: i32,
+        max_x: i32,
+        max_y: i32,
+    ) -> Result<bool, Error> {
+        let size = i64::from(self.generator.chunk_size);
+        let width = (i64::from(max_x) - i64::from(min_x) + 1) * size + 2;
+        let height = (i64::from(max_y) - i64::from(min_y) + 1) * size + 2;
+        let (Ok(width), Ok(height)) = (u16::try_from(width), u16::try_from(height)) else {
+            return Err(Error::SolverLimit(format!(
+                "materialised chunks span {}x{} cells",
+                width, height
+            )));
+        };
+
+        let (origin_x, origin_y) = self.generator.origin((min_x, min_y));
+        let origin = (origin_x - 1, origin_y - 1);
+
+        let mut board = GameBoard::from_partial_view(
+            self.view(origin, width, height)?,
+            WindowOracle {
+                generator: self.generator,
+                origin,
+                chunks: &self.chunks,
+                generated: HashMap::new(),
+            },
+        )?;
+        let progress = board.simple_solve_step()?
+            || board.pair_solve_step()?
+            || board.linear_solve_step()?
+            || board.permute_solve_step()?;
+        let grid = board.grid;
+        let generated = board.oracle.generated;
+
+        let size = self.generator.chunk_size;
+        for (chunk, solved) in generated {
+            self.chunks.entry(chunk).or_insert(Chunk {
+                solved,
+                grid: Grid::new(size, size, Cell::Unknown),
+            });
+        }
+
+        // zeros on the border of the window still have neighbours to flood fill outside of it
+        let mut zeros = Vec::new();
+        for cell in grid.get_iter() {
+            if *cell.val == Cell::Unknown {
+                continue;
+            }
+
+            let (x, y) = cell.pos;
+            let world = (origin.0 + i32::from(x), origin.1 + i32::from(y));
+            self.set_cell(world.0, world.1, *cell.val)?;
+
+            let border = x == 0 || y == 0 || x == width - 1 || y == height - 1;
+            if border && *cell.val == Cell::Number(0) {
+                zeros.push(world);
+            }
+        }
+        self.flood_fill(zeros)?;
+
+        Ok(progress)
+    }
+}
+
+/// Answers reveals inside a `ChunkedBoard::solve_step` window, generating the chunks that the
+/// window's border reaches into.
+struct WindowOracle<'a> {
+    generator: ChunkGenerator,
+    // world position of the window's bottom left cell
+    origin: (i32, i32),
+    chunks: &'a HashMap<(i32, i32), Chunk>,
+    generated: HashMap<(i32, i32), Grid<Cell>>,
+}
+
+impl RevealOracle for WindowOracle<'_> {
+    fn reveal(&mut self, x: i32, y: i32) -> Result<Cell, Error> {
+        let (chunk, (local_x, local_y)) =
+            self.generator.locate(self.origin.0 + x, self.origin.1 + y);
+
+        if !self.chunks.contains_key(&chunk) && !self.generated.contains_key(&chunk) {
+            self.generated
+                .insert(chunk, self.generator.solve_chunk(chunk)?);
+        }
+
+        let solved = match self.chunks.get(&chunk) {
+            Some(chunk) => &chunk.solved,
+            None => &self.generated[&chunk],
+        };
+        solved
+            .get_cell(local_x.into(), local_y.into())
+            .ok_or(Error::OutOfBounds { x, y })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_consistent(board: &ChunkedBoard) {
+        for chunk in board.chunks() {
+            let (origin_x, origin_y) = board.generator.origin(chunk);
+            let solved = board.generator.solve_chunk(chunk).unwrap();
+            let size = board.generator.chunk_size();
+
+            let grid = board.view((origin_x, origin_y), size, size).unwrap();
+            for cell in grid.get_iter() {
+                let (x, y) = cell.pos;
+                let expected = solved.get_cell(x.into(), y.into()).unwrap();
+                match cell.val {
+                    Cell::Unknown => (),
+                    Cell::Flag => assert_eq!(expected, Cell::Bomb, "bad flag at ({}, {})", x, y),
+                    _ => assert_eq!(*cell.val, expected, "bad reveal at ({}, {})", x, y),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn generator_1() {
+        let generator = ChunkGenerator::new(1234, 16, 40).unwrap();
+        let other = ChunkGenerator::new(1234, 16, 40).unwrap();
+
+        for chunk in [(0, 0), (-1, 0), (3, -7)] {
+            let bombs = generator.bombs(chunk);
+            assert_eq!(bombs, other.bombs(chunk));
+            assert_eq!(bombs.iter().filter(|&&bomb| bomb).count(), 40);
+        }
+        assert_ne!(generator.bombs((1, 0)), generator.bombs((0, 1)));
+        assert_ne!(
+            generator.bombs((0, 0)),
+            ChunkGenerator::new(1235, 16, 40).unwrap().bombs((0, 0))
+        );
+
+        assert_eq!(generator.locate(-1, 16), ((-1, 1), (15, 0)));
+        assert!(ChunkGenerator::new(1, 4, 2).is_ok());
+        assert!(ChunkGenerator::new(1, 4, 1).is_err());
+        assert!(ChunkGenerator::new(1, 4, 13).is_err());
+    }
+
+    #[test]
+    fn generator_2() {
+        // the numbers along a chunk's edge count the bombs of its neighbours
+        let generator = ChunkGenerator::new(7, 4, 6).unwrap();
+
+        for chunk in [(0, 0), (-1, -1), (2, 1)] {
+            let solved = generator.solve_chunk(chunk).unwrap();
+            let (origin_x, origin_y) = generator.origin(chunk);
+
+            for cell in solved.get_iter() {
+                let (x, y) = (
+                    origin_x + i32::from(cell.pos.0),
+                    origin_y + i32::from(cell.pos.1),
+                );
+                let is_bomb = |x: i32, y: i32| {
+                    let (chunk, (local_x, local_y)) = generator.locate(x, y);
+                    generator.bombs(chunk)[usize::from(local_y) * 4 + usize::from(local_x)]
+                };
+
+                assert_eq!(*cell.val, cell_from_bombs(x, y, is_bomb));
+            }
+        }
+    }
+
+    #[test]
+    fn chunked_1() {
+        let mut board = ChunkedBoard::new(ChunkGenerator::new(3, 8, 8).unwrap());
+        assert_eq!(board.chunks().count(), 0);
+
+        assert!(board.reveal(0, 0).unwrap());
+        assert_eq!(board.get_cell(0, 0), Cell::Number(0));
+
+        // the opening spreads across chunk boundaries in every direction
+        let chunks: Vec<(i32, i32)> = board.chunks().collect();
+        assert!(chunks.len() > 4);
+        assert!(chunks.iter().any(|&(x, y)| x < 0 || y < 0));
+        assert_consistent(&board);
+
+        // a cell far away from everything only materialises its own chunk
+        let before = board.chunks().count();
+        board.flag(10_000, -10_000).unwrap();
+        assert_eq!(board.chunks().count(), before + 1);
+        assert_eq!(board.get_cell(10_000, -10_000), Cell::Flag);
+    }
+
+    #[test]
+    fn chunked_3() {
+        // two groups of chunks too far apart to share a window still get solved separately
+        let generator = ChunkGenerator::new(11, 8, 10).unwrap();
+        let mut board = ChunkedBoard::new(generator);
+        board.reveal(0, 0).unwrap();
+        board.flag(100_000, 0).unwrap();
+
+        let mut near = ChunkedBoard::new(generator);
+        near.reveal(0, 0).unwrap();
+
+        assert!(board.solve_step().unwrap());
+        assert!(near.solve_step().unwrap());
+        assert_consistent(&board);
+
+        // the far flag changes nothing about the window around the opening
+        assert_eq!(
+            board.view((-40, -40), 80, 80).unwrap(),
+            near.view((-40, -40), 80, 80).unwrap()
+        );
+    }
+
+    #[test]
+    fn chunked_2() {
+        let generator = ChunkGenerator::new(11, 8, 10).unwrap();
+        let mut board = ChunkedBoard::new(generator);
+        board.reveal(0, 0).unwrap();
+
+        let mut steps = 0;
+        while steps < 20 && board.solve_step().unwrap() {
+            steps += 1;
+            assert_consistent(&board);
+        }
+        assert!(steps > 0);
+
+        // a separate board with the same world seed agrees cell for cell
+        let mut other = ChunkedBoard::new(generator);
+        for chunk in board.chunks() {
+            let (origin_x, origin_y) = generator.origin(chunk);
+            for y in 0..8 {
+                for x in 0..8 {
+                    let (x, y) = (origin_x + x, origin_y + y);
+                    if let Cell::Number(_) = board.get_cell(x, y) {
+                        assert!(other.reveal(x, y).unwrap());
+                        assert_eq!(other.get_cell(x, y), board.get_cell(x, y));
+                    }
+                }
+            }
+        }
+    }
+}
@@ -12,6 +12,12 @@ pub fn colour_str(str: &str, rgb: (u8, u8, u8), background: Option<(u8, u8, u8)>
 
 pub trait Coloured {
     fn to_coloured(&self, background: Option<(u8, u8, u8)>) -> String;
+
+    /// How many characters wide the value is once coloured, which the colour codes don't count
+    /// towards.
+    fn width(&self) -> usize {
+        1
+    }
 }
 
 impl Coloured for String {
@@ -32,6 +38,10 @@ impl Coloured for String {
             _ => colour_str(self, (255, 255, 255), background),
         }
     }
+
+    fn width(&self) -> usize {
+        self.chars().count()
+    }
 }
 
 impl Coloured for Option<u8> {
@@ -41,4 +51,17 @@ impl Coloured for Option<u8> {
             Some(num) => num.to_string().to_coloured(background),
         }
     }
+
+    fn width(&self) -> usize {
+        self.map_or(1, |num| num.to_string().len())
+    }
+}
+
+impl Coloured for f64 {
+    fn to_coloured(&self, background: Option<(u8, u8, u8)>) -> String {
+        // show the probability in tenths (9 meaning 90% or more), fading from green to red
+        let tenths = (self * 10.0).floor().clamp(0.0, 9.0) as u8;
+        let red = (self.clamp(0.0, 1.0) * 255.0) as u8;
+        colour_str(&tenths.to_string(), (red, 255 - red, 0), background)
+    }
 }
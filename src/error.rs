@@ -0,0 +1,48 @@
+use std::fmt::Display;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// malformed board text, `line` and `column` are 1-based
+    Parse {
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    /// a coordinate outside of the grid
+    OutOfBounds { x: i32, y: i32 },
+    /// the board contradicts itself, eg. mismatched grids or numbers that cannot be satisfied
+    InconsistentBoard(String),
+    /// the solver gave up on a position it could not handle, eg. a frontier too complex to search
+    SolverLimit(String),
+    /// a `RevealOracle` could not reveal a cell, eg. the external game stopped responding
+    Oracle(String),
+    /// a board could not be generated with the requested parameters
+    Generation(String),
+    /// an unknown strategy or strategy option, an option value that could not be parsed, or an
+    /// invalid custom neighbourhood
+    Config(String),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Parse {
+                line,
+                column,
+                message,
+            } => write!(
+                f,
+                "parse error at line {}, column {}: {}",
+                line, column, message
+            ),
+            Error::OutOfBounds { x, y } => write!(f, "({}, {}) is out of bounds", x, y),
+            Error::InconsistentBoard(message) => write!(f, "inconsistent board: {}", message),
+            Error::SolverLimit(message) => write!(f, "solver limit: {}", message),
+            Error::Oracle(message) => write!(f, "oracle error: {}", message),
+            Error::Generation(message) => write!(f, "could not generate board: {}", message),
+            Error::Config(message) => write!(f, "invalid configuration: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
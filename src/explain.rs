@@ -0,0 +1,337 @@
+use std::fmt::Display;
+
+use serde::Serialize;
+
+use crate::{
+    Cell,
+    grid::{Grid, Highlights},
+};
+
+const BOMB_HIGHLIGHT: (u8, u8, u8) = (150, 50, 50);
+const SAFE_HIGHLIGHT: (u8, u8, u8) = (50, 150, 50);
+const NUMBER_HIGHLIGHT: (u8, u8, u8) = (150, 150, 50);
+
+/// The rule a deduction was made with.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Rule {
+    /// the number needs exactly as many more bombs as it has unknown neighbours
+    AllBombs {
+        number: (u16, u16),
+        value: u8,
+        flags: usize,
+        unknown: usize,
+    },
+    /// the number's flags already account for all of its bombs
+    AllSafe {
+        number: (u16, u16),
+        value: u8,
+        flags: usize,
+    },
+    /// `more` needs `difference` more bombs than `less` and has exactly that many unknown
+    /// neighbours that `less` does not touch, so they are all bombs and the unknown neighbours of
+    /// `less` that `more` does not touch are all safe. with a difference of 0 the unknown neighbours
+    /// of `more` are a subset of those of `less`
+    Pair {
+        less: (u16, u16),
+        more: (u16, u16),
+        difference: u8,
+    },
+    /// row reducing the equations of `numbers` (and the remaining bomb count if `bomb_count` is set)
+    /// gave an equation over `cells` unknown cells with only one solution
+    Linear {
+        numbers: Vec<(u16, u16)>,
+        cells: usize,
+        bomb_count: bool,
+    },
+    /// the frontier as CNF (and the remaining bomb count if `bomb_count` is set) is unsatisfiable
+    /// with the cell set the other way
+    Sat {
+        numbers: Vec<(u16, u16)>,
+        bomb_count: bool,
+    },
+    /// the cell is the same in every valid bomb pattern of its frontier component
+    Component {
+        /// every number cell constraining the component
+        numbers: Vec<(u16, u16)>,
+        cells: usize,
+        /// the number of valid patterns, only counting those that fit the remaining bomb count when
+        /// `bomb_count` is set
+        valid_patterns: u64,
+        bomb_count: bool,
+    },
+    /// every valid frontier pattern leaves either no bombs or only bombs for the interior
+    BombCount { remaining: u32, interior: usize },
+}
+
+/// Why a cell is known to be a bomb or known to be safe.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Explanation {
+    pub cell: (u16, u16),
+    pub bomb: bool,
+    pub rule: Rule,
+}
+
+impl Explanation {
+    /// The numbered cells the deduction depends on.
+    pub fn numbers(&self) -> Vec<(u16, u16)> {
+        match &self.rule {
+            Rule::AllBombs { number, .. } | Rule::AllSafe { number, .. } => vec![*number],
+            Rule::Pair { less, more, .. } => vec![*less, *more],
+            Rule::Linear { numbers, .. }
+            | Rule::Sat { numbers, .. }
+            | Rule::Component { numbers, .. } => numbers.clone(),
+            Rule::BombCount { .. } => Vec::new(),
+        }
+    }
+
+    /// The deduced cell (red for a bomb, green if safe) and the numbers it depends on (yellow), for
+    /// `Grid::to_string`.
+    pub fn highlights(&self) -> Highlights {
+        let mut highlights = Highlights::new();
+        for number in self.numbers() {
+            highlights.insert(number, NUMBER_HIGHLIGHT);
+        }
+        highlights.insert(
+            self.cell,
+            if self.bomb {
+                BOMB_HIGHLIGHT
+            } else {
+                SAFE_HIGHLIGHT
+            },
+        );
+
+        highlights
+    }
+
+    /// The explanation followed by `grid` with the cells involved highlighted.
+    pub fn render(&self, grid: &Grid<Cell>) -> String {
+        format!("{}\n{}", self, grid.to_string(Some(self.highlights())))
+    }
+}
+
+impl Display for Explanation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (x, y) = self.cell;
+        let state = if self.bomb { "a bomb" } else { "safe" };
+        write!(f, "({}, {}) is {}: ", x, y, state)?;
+
+        match &self.rule {
+            Rule::AllBombs {
+                number: (num_x, num_y),
+                value,
+                flags: 0,
+                unknown,
+            } => write!(
+                f,
+                "{} at ({}, {}) has exactly {} unknown neighbours",
+                value, num_x, num_y, unknown
+            ),
+            Rule::AllBombs {
+                number: (num_x, num_y),
+                value,
+                flags,
+                unknown,
+            } => write!(
+                f,
+                "{} at ({}, {}) has {} flagged and exactly {} unknown neighbours",
+                value, num_x, num_y, flags, unknown
+            ),
+            Rule::AllSafe {
+                number: (num_x, num_y),
+                value,
+                flags,
+            } => write!(
+                f,
+                "{} at ({}, {}) already has {} flagged neighbours",
+                value, num_x, num_y, flags
+            ),
+            Rule::Pair {
+                less: (less_x, less_y),
+                more: (more_x, more_y),
+                difference: 0,
+            } => write!(
+                f,
+                "({}, {}) needs as many bombs as ({}, {}) and all of its unknown neighbours are \
+                 next to ({}, {})",
+                more_x, more_y, less_x, less_y, less_x, less_y
+            ),
+            Rule::Pair {
+                less: (less_x, less_y),
+                more: (more_x, more_y),
+                difference,
+            } => write!(
+                f,
+                "({}, {}) needs {} more bombs than ({}, {}) and has exactly {} unknown neighbours \
+                 that ({}, {}) does not touch",
+                more_x, more_y, difference, less_x, less_y, difference, less_x, less_y
+            ),
+            Rule::Linear {
+                numbers,
+                cells,
+                bomb_count,
+            } => write!(
+                f,
+                "combining the equations of {} numbers{} leaves {} unknown cells that can only be \
+                 filled one way",
+                numbers.len(),
+                if *bomb_count {
+                    " and the remaining bomb count"
+                } else {
+                    ""
+                },
+                cells
+            ),
+            Rule::Sat {
+                numbers,
+                bomb_count,
+            } => write!(
+                f,
+                "no way of satisfying all {} frontier numbers{} has ({}, {}) {}",
+                numbers.len(),
+                if *bomb_count {
+                    " and the remaining bomb count"
+                } else {
+                    ""
+                },
+                x,
+                y,
+                if self.bomb { "safe" } else { "as a bomb" }
+            ),
+            Rule::Component {
+                cells,
+                valid_patterns,
+                bomb_count,
+                ..
+            } => write!(
+                f,
+                "in all {} valid configurations of this {} cell component{} ({}, {}) is {}",
+                valid_patterns,
+                cells,
+                if *bomb_count {
+                    " that fit the remaining bomb count"
+                } else {
+                    ""
+                },
+                x,
+                y,
+                state
+            ),
+            Rule::BombCount {
+                remaining,
+                interior,
+            } if self.bomb => write!(
+                f,
+                "every valid frontier pattern leaves exactly {} of the {} remaining bombs for the \
+                 {} cells away from the frontier",
+                interior, remaining, interior
+            ),
+            Rule::BombCount {
+                remaining,
+                interior,
+            } => write!(
+                f,
+                "every valid frontier pattern uses all {} remaining bombs, leaving none for the {} \
+                 cells away from the frontier",
+                remaining, interior
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+    use crate::{board::GameBoard, fixtures::get_count_safe_pre_str, solve::deduce};
+
+    #[test]
+    fn explain_place_flags_1() {
+        let mut board = GameBoard::from_str(
+            "1  1
+            B  1",
+            "1  1
+            ?  1",
+        )
+        .unwrap();
+
+        assert!(board.simple_solve_step().unwrap());
+
+        let explanations = board.explanations();
+        assert_eq!(explanations.len(), 1);
+        assert_eq!(explanations[0].cell, (0, 0));
+        assert!(explanations[0].bomb);
+        assert!(matches!(
+            explanations[0].rule,
+            Rule::AllBombs {
+                value: 1,
+                flags: 0,
+                unknown: 1,
+                ..
+            }
+        ));
+
+        let highlights = explanations[0].highlights();
+        assert_eq!(highlights.get(&(0, 0)), Some(&BOMB_HIGHLIGHT));
+        assert_eq!(
+            highlights.get(&explanations[0].numbers()[0]),
+            Some(&NUMBER_HIGHLIGHT)
+        );
+    }
+
+    #[test]
+    fn explain_chord_1() {
+        let mut board = GameBoard::from_str("B  1  0", "F  1  ?").unwrap();
+
+        board.chord(1, 0).unwrap();
+
+        assert_eq!(
+            board.take_explanations(),
+            vec![Explanation {
+                cell: (2, 0),
+                bomb: false,
+                rule: Rule::AllSafe {
+                    number: (1, 0),
+                    value: 1,
+                    flags: 1,
+                },
+            }]
+        );
+        assert!(board.explanations().is_empty());
+    }
+
+    #[test]
+    fn explain_deductions_1() {
+        let grid = Grid::from_str(get_count_safe_pre_str()).unwrap();
+
+        let deductions = deduce(&grid, 1).unwrap();
+        assert_eq!(
+            deductions.explanations.len(),
+            deductions.safe.len() + deductions.bombs.len()
+        );
+
+        let bomb = deductions
+            .explanations
+            .iter()
+            .find(|explanation| explanation.cell == (1, 2))
+            .unwrap();
+        assert_eq!(
+            bomb.to_string(),
+            "(1, 2) is a bomb: in all 1 valid configurations of this 5 cell component (1, 2) is a bomb"
+        );
+        assert_eq!(bomb.numbers(), vec![(2, 0), (2, 1), (2, 2), (2, 3), (2, 4)]);
+
+        let interior = deductions
+            .explanations
+            .iter()
+            .find(|explanation| explanation.cell == (0, 0))
+            .unwrap();
+        assert_eq!(
+            interior.to_string(),
+            "(0, 0) is safe: every valid frontier pattern uses all 1 remaining bombs, leaving none \
+             for the 5 cells away from the frontier"
+        );
+        assert!(interior.render(&grid).starts_with(&interior.to_string()));
+    }
+}
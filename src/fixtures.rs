@@ -0,0 +1,18 @@
+//! Boards shared by the tests of several modules.
+
+// one bomb behind a column of numbers, where only the total bomb count shows which cells are safe
+pub fn get_count_safe_solved_str() -> &'static str {
+    "0  0  0  0  0
+    1  1  1  0  0
+    1  B  1  0  0
+    1  1  1  0  0
+    0  0  0  0  0"
+}
+
+pub fn get_count_safe_pre_str() -> &'static str {
+    "?  ?  0  0  0
+    ?  ?  1  0  0
+    ?  ?  1  0  0
+    ?  ?  1  0  0
+    ?  ?  0  0  0"
+}
@@ -1,160 +1,223 @@
-use std::{collections::HashMap, fmt::Display, hash::Hash, vec};
+use std::{collections::HashMap, fmt::Display, hash::Hash, str::FromStr, vec};
 
-use crate::{Cell, colour::Coloured, utils::unflatten};
+use crate::{Cell, colour::Coloured, error::Error, neighbourhood::Neighbourhood};
 use std::collections::HashSet;
 
+pub type Highlights = HashMap<(u16, u16), (u8, u8, u8)>;
+
+/// How the edges of a grid connect, which decides the neighbours of the cells along them.
+#[derive(Clone, Copy, Debug, Default, Hash, PartialEq, Eq)]
+pub enum Topology {
+    /// cells along the edges have fewer neighbours
+    #[default]
+    Bounded,
+    /// the left and right edges are joined, so every row wraps around
+    Cylinder,
+    /// both pairs of opposite edges are joined, so rows and columns wrap around
+    Torus,
+}
+
+impl Topology {
+    // (x, y) moved onto a width x height grid across any joined edges, `None` if it is off the grid
+    fn wrap(self, x: i32, y: i32, width: u16, height: u16) -> Option<(u16, u16)> {
+        let (width, height) = (i32::from(width), i32::from(height));
+        let (x, y) = match self {
+            Topology::Bounded => (x, y),
+            Topology::Cylinder => (x.rem_euclid(width), y),
+            Topology::Torus => (x.rem_euclid(width), y.rem_euclid(height)),
+        };
+
+        if x >= 0 && x < width && y >= 0 && y < height {
+            Some((x as u16, y as u16))
+        } else {
+            None
+        }
+    }
+}
+
 #[derive(Clone, Debug, Hash, PartialEq, Eq)]
-pub struct Grid<T: Clone + Coloured + Hash + PartialEq + Eq> {
-    cells: Vec<Vec<T>>,
-    pub width: u8,
-    pub height: u8,
+pub struct Grid<T: Clone + Coloured> {
+    // row by row starting from the bottom row, so (x, y) is at y * width + x
+    cells: Vec<T>,
+    pub width: u16,
+    pub height: u16,
+    pub topology: Topology,
+    pub neighbourhood: Neighbourhood,
 }
 
-impl<T: Clone + Coloured + Hash + PartialEq + Eq> Grid<T> {
-    pub fn new(width: u8, height: u8, fill_cell: T) -> Self {
+impl<T: Clone + Coloured> Grid<T> {
+    pub fn new(width: u16, height: u16, fill_cell: T) -> Self {
         Grid {
-            cells: vec![vec![fill_cell; width as usize]; height as usize],
+            cells: vec![fill_cell; width as usize * height as usize],
             width,
             height,
+            topology: Topology::Bounded,
+            neighbourhood: Neighbourhood::Moore,
         }
     }
 
-    pub fn from(cells: Vec<Vec<T>>, width: u8, height: u8) -> Result<Self, String> {
+    /// The same grid with its edges joined according to `topology`.
+    pub fn with_topology(mut self, topology: Topology) -> Self {
+        self.topology = topology;
+        self
+    }
+
+    /// The same grid with neighbours (and so numbers) taken from `neighbourhood`.
+    pub fn with_neighbourhood(mut self, neighbourhood: Neighbourhood) -> Self {
+        self.neighbourhood = neighbourhood;
+        self
+    }
+
+    /// A grid of the same size, topology and neighbourhood, filled with `fill_cell`.
+    pub fn new_like<U: Clone + Coloured>(&self, fill_cell: U) -> Grid<U> {
+        Grid::new(self.width, self.height, fill_cell)
+            .with_topology(self.topology)
+            .with_neighbourhood(self.neighbourhood.clone())
+    }
+
+    /// Builds a grid from rows listed top to bottom, the way boards are written out.
+    pub fn from(cells: Vec<Vec<T>>, width: u16, height: u16) -> Result<Self, Error> {
         if cells.len() != height as usize {
-            return Err("cells.len != height".to_string());
+            return Err(Error::InconsistentBoard(format!(
+                "expected {} rows, found {}",
+                height,
+                cells.len()
+            )));
         }
         for row in &cells {
             if row.len() != width as usize {
-                return Err("row != height".to_string());
+                return Err(Error::InconsistentBoard(format!(
+                    "expected rows of {} cells, found {}",
+                    width,
+                    row.len()
+                )));
             }
         }
 
         Ok(Grid {
-            cells,
+            cells: cells.into_iter().rev().flatten().collect(),
             width,
             height,
+            topology: Topology::Bounded,
+            neighbourhood: Neighbourhood::Moore,
         })
     }
 
-    fn assert_bounds(&self, x: i16, y: i16) -> Result<(), String> {
-        if x < 0 || x >= self.width.into() {
-            Err("x is out of bounds".to_string())
-        } else if y < 0 || y >= self.height.into() {
-            Err("y is out of bounds".to_string())
+    fn assert_bounds(&self, x: i32, y: i32) -> Result<(), Error> {
+        if x < 0 || x >= self.width.into() || y < 0 || y >= self.height.into() {
+            Err(Error::OutOfBounds { x, y })
         } else {
             Ok(())
         }
     }
 
-    pub fn get_cell(&self, x: i16, y: i16) -> Option<T> {
-        if self.assert_bounds(x, y).is_err() {
-            return None;
-        }
+    fn index(&self, x: i32, y: i32) -> Option<usize> {
+        self.assert_bounds(x, y).ok()?;
 
-        let cell = self
-            .cells
-            .get(((self.height as i16) - y - 1) as usize)
-            .unwrap()
-            .get(x as usize)
-            .cloned()
-            .unwrap();
+        Some(y as usize * self.width as usize + x as usize)
+    }
 
-        Some(cell)
+    /// A reference to the cell at (x, y), or `None` if it is off the grid.
+    pub fn get(&self, x: i32, y: i32) -> Option<&T> {
+        self.index(x, y).map(|i| &self.cells[i])
     }
 
-    pub fn set_cell(&mut self, x: i16, y: i16, cell_value: T) -> Result<(), String> {
-        self.assert_bounds(x, y)?;
+    pub fn get_cell(&self, x: i32, y: i32) -> Option<T> {
+        self.get(x, y).cloned()
+    }
+
+    pub fn set_cell(&mut self, x: i32, y: i32, cell_value: T) -> Result<(), Error> {
+        let i = self.index(x, y).ok_or(Error::OutOfBounds { x, y })?;
 
-        self.cells[(self.height - (y as u8) - 1) as usize][x as usize] = cell_value;
+        self.cells[i] = cell_value;
 
         Ok(())
     }
 
-    pub fn get_iter(&self) -> CellsIter<T> {
+    /// Every cell with its position, row by row from the bottom left.
+    pub fn get_iter(&self) -> CellsIter<'_, T> {
         CellsIter {
             curr_pos: (0, 0),
             width: self.width,
-            height: self.height,
-            cells: self.cells.clone(),
+            cells: self.cells.iter(),
         }
     }
 
-    pub fn adj_cells(
-        &self,
-        x: u8,
-        y: u8,
-        filter_cells: Option<HashSet<T>>,
-    ) -> Result<Vec<(u8, u8)>, String> {
-        let mut out: Vec<(u8, u8)> = Vec::new();
-        let deltas: [(i8, i8); 8] = [
-            (-1, 1),
-            (0, 1),
-            (1, 1),
-            (-1, 0),
-            (1, 0),
-            (-1, -1),
-            (0, -1),
-            (1, -1),
-        ];
-
-        for d in deltas {
-            if let Some(elem) = self.get_cell(x as i16 + d.0 as i16, y as i16 + d.1 as i16) {
-                if let Some(ref filter) = filter_cells {
-                    if filter.contains(&elem) {
-                        out.push(((x as i16 + d.0 as i16) as u8, (y as i16 + d.1 as i16) as u8));
-                    }
-                } else {
-                    out.push(((x as i16 + d.0 as i16) as u8, (y as i16 + d.1 as i16) as u8));
-                }
-            }
+    /// The cells of row `y` from left to right.
+    pub fn row(&self, y: u16) -> Option<&[T]> {
+        if y >= self.height {
+            return None;
         }
 
-        Ok(out)
+        let start = y as usize * self.width as usize;
+        Some(&self.cells[start..start + self.width as usize])
     }
 
-    pub fn diff(&self, other: &Self) -> Result<Vec<(u8, u8)>, String> {
-        let mut out = Vec::new();
-
-        if self.width != other.width {
-            return Err("self.width != other.width".to_string());
-        } else if self.height != other.height {
-            return Err("height != other.height".to_string());
+    /// The cells of column `x` from the bottom up.
+    pub fn column(&self, x: u16) -> Option<impl Iterator<Item = &T>> {
+        if x >= self.width {
+            return None;
         }
 
-        for cell in self.get_iter() {
-            let (x, y) = cell.pos;
-            if let Some(other_cell) = other.get_cell(x.into(), y.into()) {
-                if cell.val != other_cell {
-                    out.push(cell.pos);
-                }
-            }
-        }
+        Some(self.cells[x as usize..].iter().step_by(self.width as usize))
+    }
 
-        Ok(out)
+    /// The positions of the cells in the neighbourhood of (x, y) that are on the grid, wrapping
+    /// around joined edges. Each cell is listed once even when a narrow grid wraps onto itself, and
+    /// (x, y) is never its own neighbour.
+    pub fn neighbours(&self, x: u16, y: u16) -> Neighbours {
+        // only grids at most twice as wide (or high) as the reach of the neighbourhood can wrap
+        // different deltas onto the same cell, or onto the cell itself
+        let span = 2 * u32::from(self.neighbourhood.reach());
+        let overlapping = self.topology != Topology::Bounded
+            && (u32::from(self.width) <= span || u32::from(self.height) <= span);
+
+        Neighbours {
+            pos: (x.into(), y.into()),
+            width: self.width,
+            height: self.height,
+            topology: self.topology,
+            neighbourhood: self.neighbourhood.clone(),
+            overlapping,
+            next: 0,
+        }
     }
 
-    pub fn to_string(&self, highlights: Option<HashMap<(u8, u8), (u8, u8, u8)>>) -> String {
+    pub fn to_string(&self, highlights: Option<Highlights>) -> String {
         let mut out = String::new();
 
-        for row in 0..self.height {
-            for col in 0..self.width {
-                let elem = self.cells[row as usize][col as usize].clone();
+        // every cell is as wide as the widest one (eg. a 10 on a large neighbourhood), so the
+        // columns line up
+        let cell_width = self.cells.iter().map(T::width).max().unwrap_or(1);
+        // hex rows are staggered by half a cell, padded so the row numbers line up
+        let hex = self.neighbourhood == Neighbourhood::Hex;
+        let stagger = " ".repeat((cell_width + 2) / 2);
+
+        for y in (0..self.height).rev() {
+            if hex && y % 2 == 1 {
+                out.push_str(&stagger);
+            }
 
+            for (col, elem) in self.row(y).unwrap_or_default().iter().enumerate() {
                 let mut bg: Option<(u8, u8, u8)> = None;
                 if let Some(ref map) = highlights {
-                    bg = map.get(&(col, self.height - row - 1)).copied();
+                    bg = map.get(&(col as u16, y)).copied();
                 }
 
-                out.push_str(format!(" {} ", elem.to_coloured(bg)).as_str());
+                let padding = " ".repeat(cell_width - elem.width());
+                out.push_str(format!(" {}{} ", padding, elem.to_coloured(bg)).as_str());
             }
 
-            out.push_str(format!(" | {}\n", self.height - row - 1).as_str());
+            if hex && y % 2 == 0 {
+                out.push_str(&stagger);
+            }
+            out.push_str(format!(" | {}\n", y).as_str());
         }
 
         out.push_str(" -");
 
         for _ in 0..self.width {
-            out.push_str("---");
+            out.push_str(&"-".repeat(cell_width + 2));
         }
 
         out.push('\n');
@@ -162,7 +225,7 @@ impl<T: Clone + Coloured + Hash + PartialEq + Eq> Grid<T> {
         let max_digits = self.width.to_string().len();
         for digit in 0..max_digits {
             for col in 0..self.width {
-                out.push(' ');
+                out.push_str(&" ".repeat(cell_width));
                 out.push_str(
                     format!("{:0width$}", col, width = max_digits)
                         .chars()
@@ -180,136 +243,258 @@ impl<T: Clone + Coloured + Hash + PartialEq + Eq> Grid<T> {
     }
 }
 
-impl Grid<Cell> {
-    pub fn from_str(input_str: &str) -> Result<Self, String> {
-        let height = input_str.split("\n").count();
-        let width = input_str.split("\n").collect::<Vec<_>>()[0]
-            .trim()
-            .split(" ")
-            .filter(|c| !c.is_empty())
-            .count();
-
-        if height > 255 {
-            return Err("height > 255".to_string());
-        } else if width > 255 {
-            return Err("width > 255".to_string());
+impl<T: Clone + Coloured + Hash + PartialEq + Eq> Grid<T> {
+    pub fn adj_cells(
+        &self,
+        x: u16,
+        y: u16,
+        filter_cells: Option<HashSet<T>>,
+    ) -> Result<Vec<(u16, u16)>, Error> {
+        self.assert_bounds(x.into(), y.into())?;
+
+        Ok(self
+            .neighbours(x, y)
+            .filter(|&(adj_x, adj_y)| match filter_cells {
+                Some(ref filter) => self
+                    .get(adj_x.into(), adj_y.into())
+                    .is_some_and(|elem| filter.contains(elem)),
+                None => true,
+            })
+            .collect())
+    }
+
+    pub fn diff(&self, other: &Self) -> Result<Vec<(u16, u16)>, Error> {
+        let mut out = Vec::new();
+
+        if self.width != other.width || self.height != other.height {
+            return Err(Error::InconsistentBoard(format!(
+                "cannot diff a {}x{} grid with a {}x{} grid",
+                self.width, self.height, other.width, other.height
+            )));
+        }
+
+        for cell in self.get_iter() {
+            let (x, y) = cell.pos;
+            if let Some(other_cell) = other.get(x.into(), y.into())
+                && cell.val != other_cell
+            {
+                out.push(cell.pos);
+            }
         }
 
-        let height = height as u8;
-        let width = width as u8;
-
-        let mut flat_cells: Vec<Cell> = Vec::new();
-
-        for row in input_str.split("\n") {
-            for cell in row.trim().split(" ").filter(|c| !c.is_empty()) {
-                flat_cells.push(match cell {
-                    "0" => Cell::Number(0),
-                    "1" => Cell::Number(1),
-                    "2" => Cell::Number(2),
-                    "3" => Cell::Number(3),
-                    "4" => Cell::Number(4),
-                    "5" => Cell::Number(5),
-                    "6" => Cell::Number(6),
-                    "7" => Cell::Number(7),
-                    "8" => Cell::Number(8),
-                    "B" => Cell::Bomb,
-                    "F" => Cell::Flag,
-                    "?" => Cell::Unknown,
-                    "*" => Cell::Asterix,
-                    _ => return Err(format!("unknown character: `{}`", cell)),
+        Ok(out)
+    }
+}
+
+impl FromStr for Grid<Cell> {
+    type Err = Error;
+
+    fn from_str(input_str: &str) -> Result<Self, Error> {
+        let mut cells: Vec<Vec<Cell>> = Vec::new();
+
+        for (line, row) in input_str.split("\n").enumerate() {
+            let line = line + 1;
+            let mut row_cells = Vec::new();
+
+            // track the column of each cell so errors can point at it
+            let mut column = 1;
+            for cell in row.split(" ") {
+                let cell_column = column;
+                column += cell.len() + 1;
+
+                let cell = cell.trim();
+                if cell.is_empty() {
+                    continue;
+                }
+
+                row_cells.push(Cell::from_token(cell).ok_or(Error::Parse {
+                    line,
+                    column: cell_column,
+                    message: format!("unknown character: `{}`", cell),
+                })?);
+
+                if row_cells.len() > u16::MAX.into() {
+                    return Err(Error::Parse {
+                        line,
+                        column: cell_column,
+                        message: "width > 65535".to_string(),
+                    });
+                }
+            }
+
+            if let Some(first_row) = cells.first()
+                && first_row.len() != row_cells.len()
+            {
+                return Err(Error::Parse {
+                    line,
+                    column: row.trim_end().len() + 1,
+                    message: format!(
+                        "expected {} cells, found {}",
+                        first_row.len(),
+                        row_cells.len()
+                    ),
+                });
+            }
+
+            if line > u16::MAX.into() {
+                return Err(Error::Parse {
+                    line,
+                    column: 1,
+                    message: "height > 65535".to_string(),
                 });
             }
+
+            cells.push(row_cells);
         }
 
-        let cells = unflatten(flat_cells, width, height)?;
-        Ok(Grid::from(cells, width, height)?)
+        let height = cells.len() as u16;
+        let width = cells[0].len() as u16;
+
+        Grid::from(cells, width, height)
     }
+}
 
-    pub fn adj_bombs(&self, x: u8, y: u8) -> Result<Vec<(u8, u8)>, String> {
-        let hash_set = HashSet::from([Cell::Bomb]);
-        self.adj_cells(x, y, Some(hash_set))
+impl Grid<Cell> {
+    /// Parses a grid of `Neighbourhood::Hex` cells, in the same format as `from_str`. Rows are
+    /// usually written staggered the way `to_string` renders them, but spaces never change what
+    /// is parsed.
+    pub fn from_hex_str(input_str: &str) -> Result<Self, Error> {
+        Ok(Grid::from_str(input_str)?.with_neighbourhood(Neighbourhood::Hex))
+    }
+
+    // the neighbours of (x, y) whose cell matches `filter`
+    fn adj_matching(
+        &self,
+        x: u16,
+        y: u16,
+        filter: impl Fn(&Cell) -> bool,
+    ) -> Result<Vec<(u16, u16)>, Error> {
+        self.assert_bounds(x.into(), y.into())?;
+
+        Ok(self
+            .neighbours(x, y)
+            .filter(|&(adj_x, adj_y)| self.get(adj_x.into(), adj_y.into()).is_some_and(&filter))
+            .collect())
     }
 
-    pub fn adj_flags(&self, x: u8, y: u8) -> Result<Vec<(u8, u8)>, String> {
-        let hash_set = HashSet::from([Cell::Flag]);
-        self.adj_cells(x, y, Some(hash_set))
+    pub fn adj_bombs(&self, x: u16, y: u16) -> Result<Vec<(u16, u16)>, Error> {
+        self.adj_matching(x, y, |cell| *cell == Cell::Bomb)
     }
 
-    pub fn adj_number(&self, x: u8, y: u8) -> Result<Vec<(u8, u8)>, String> {
-        let hash_set = HashSet::from([
-            Cell::Number(1),
-            Cell::Number(2),
-            Cell::Number(3),
-            Cell::Number(4),
-            Cell::Number(5),
-            Cell::Number(6),
-            Cell::Number(7),
-            Cell::Number(8),
-        ]);
-        self.adj_cells(x, y, Some(hash_set))
+    pub fn adj_flags(&self, x: u16, y: u16) -> Result<Vec<(u16, u16)>, Error> {
+        self.adj_matching(x, y, |cell| *cell == Cell::Flag)
+    }
+
+    pub fn adj_number(&self, x: u16, y: u16) -> Result<Vec<(u16, u16)>, Error> {
+        self.adj_matching(x, y, |cell| matches!(cell, Cell::Number(1..)))
     }
 }
 
 impl Grid<Option<u8>> {
-    pub fn decr(&mut self, x: u8, y: u8) -> Result<(), String> {
-        if let Some(cell) = self.get_cell(x.into(), y.into()) {
-            if let Some(num) = cell {
-                if num <= 0 {
-                    return Err(format!(
-                        "num <= 0 | attempted to dec value at ({}, {}) = {}",
-                        x, y, num
-                    ));
-                }
-
-                self.set_cell(x.into(), y.into(), Some(num - 1))?;
+    pub fn decr(&mut self, x: u16, y: u16) -> Result<(), Error> {
+        if let Some(Some(num)) = self.get_cell(x.into(), y.into()) {
+            if num == 0 {
+                return Err(Error::InconsistentBoard(format!(
+                    "attempted to decrement flag adj value at ({}, {}) below 0",
+                    x, y
+                )));
             }
+
+            self.set_cell(x.into(), y.into(), Some(num - 1))?;
         }
 
         Ok(())
     }
 }
 
-impl<T: Clone + Coloured + Hash + PartialEq + Eq> Display for Grid<T> {
+impl<T: Clone + Coloured> Display for Grid<T> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.write_str(self.to_string(None).as_str())
     }
 }
 
 #[derive(Debug, PartialEq)]
-pub struct CellsIterValue<T: Clone + Coloured + Hash + PartialEq + Eq> {
-    pub pos: (u8, u8),
-    pub val: T,
+pub struct CellsIterValue<'a, T: Clone + Coloured> {
+    pub pos: (u16, u16),
+    pub val: &'a T,
 }
 
 #[derive(Debug)]
-pub struct CellsIter<T: Clone + Coloured + Hash + PartialEq + Eq> {
-    pub curr_pos: (u8, u8),
-    pub width: u8,
-    pub height: u8,
-    pub cells: Vec<Vec<T>>,
+pub struct CellsIter<'a, T: Clone + Coloured> {
+    pub curr_pos: (u16, u16),
+    pub width: u16,
+    pub cells: std::slice::Iter<'a, T>,
 }
 
-impl<T: Clone + Coloured + Hash + PartialEq + Eq> Iterator for CellsIter<T> {
-    type Item = CellsIterValue<T>;
+impl<'a, T: Clone + Coloured> Iterator for CellsIter<'a, T> {
+    type Item = CellsIterValue<'a, T>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.curr_pos.0 >= self.width {
-            self.curr_pos = (0, self.curr_pos.1 + 1)
-        }
-
-        if self.curr_pos.1 >= self.height {
-            return None;
-        }
+        let val = self.cells.next()?;
 
         let out = CellsIterValue {
             pos: self.curr_pos,
-            val: self.cells[(self.height - self.curr_pos.1 - 1) as usize][self.curr_pos.0 as usize]
-                .clone(),
+            val,
         };
         self.curr_pos.0 += 1;
+        if self.curr_pos.0 >= self.width {
+            self.curr_pos = (0, self.curr_pos.1 + 1)
+        }
 
         Some(out)
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.cells.size_hint()
+    }
+}
+
+/// Iterator over the positions around a cell that are on the grid, see `Grid::neighbours`.
+#[derive(Clone, Debug)]
+pub struct Neighbours {
+    pos: (i32, i32),
+    width: u16,
+    height: u16,
+    topology: Topology,
+    neighbourhood: Neighbourhood,
+    // whether deltas can wrap onto the same cell, which then has to be skipped
+    overlapping: bool,
+    // index into the deltas of the neighbourhood
+    next: usize,
+}
+
+impl Neighbours {
+    // the neighbour reached with the i-th delta, if it is on the grid
+    fn at(&self, i: usize) -> Option<(u16, u16)> {
+        let (dx, dy) = self.neighbourhood.deltas(self.pos.1)[i];
+        self.topology
+            .wrap(self.pos.0 + dx, self.pos.1 + dy, self.width, self.height)
+    }
+}
+
+impl Iterator for Neighbours {
+    type Item = (u16, u16);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.next < self.neighbourhood.deltas(self.pos.1).len() {
+            let i = self.next;
+            self.next += 1;
+
+            let Some(pos) = self.at(i) else {
+                continue;
+            };
+            if self.overlapping
+                && ((i32::from(pos.0), i32::from(pos.1)) == self.pos
+                    || (0..i).any(|j| self.at(j) == Some(pos)))
+            {
+                continue;
+            }
+
+            return Some(pos);
+        }
+
+        None
+    }
 }
 
 #[cfg(test)]
@@ -337,13 +522,16 @@ mod tests {
     fn new() {
         let grid: Grid<Cell> = Grid::new(10, 10, Cell::Unknown);
 
-        assert_eq!(grid.cells.len(), grid.height.into());
+        assert_eq!(
+            grid.cells.len(),
+            usize::from(grid.width) * usize::from(grid.height)
+        );
 
         for y in 0..grid.height {
-            assert_eq!(grid.cells[y as usize].len(), grid.width.into());
+            assert_eq!(grid.row(y).unwrap().len(), usize::from(grid.width));
 
             for x in 0..grid.width {
-                assert_eq!(grid.cells[y as usize][x as usize], Cell::Unknown);
+                assert_eq!(grid.get(x.into(), y.into()), Some(&Cell::Unknown));
             }
         }
     }
@@ -359,44 +547,72 @@ mod tests {
             vec![
                 CellsIterValue {
                     pos: (0, 0),
-                    val: Cell::Number(0)
+                    val: &Cell::Number(0)
                 },
                 CellsIterValue {
                     pos: (1, 0),
-                    val: Cell::Unknown
+                    val: &Cell::Unknown
                 },
                 CellsIterValue {
                     pos: (2, 0),
-                    val: Cell::Number(5)
+                    val: &Cell::Number(5)
                 },
                 CellsIterValue {
                     pos: (0, 1),
-                    val: Cell::Unknown
+                    val: &Cell::Unknown
                 },
                 CellsIterValue {
                     pos: (1, 1),
-                    val: Cell::Bomb
+                    val: &Cell::Bomb
                 },
                 CellsIterValue {
                     pos: (2, 1),
-                    val: Cell::Unknown
+                    val: &Cell::Unknown
                 },
                 CellsIterValue {
                     pos: (0, 2),
-                    val: Cell::Unknown
+                    val: &Cell::Unknown
                 },
                 CellsIterValue {
                     pos: (1, 2),
-                    val: Cell::Unknown
+                    val: &Cell::Unknown
                 },
                 CellsIterValue {
                     pos: (2, 2),
-                    val: Cell::Flag
+                    val: &Cell::Flag
                 },
             ]
         );
     }
 
+    #[test]
+    fn rows_1() {
+        let grid = generate_grid();
+
+        assert_eq!(
+            grid.row(0),
+            Some(&[Cell::Number(0), Cell::Unknown, Cell::Number(5)][..])
+        );
+        assert_eq!(
+            grid.row(2),
+            Some(&[Cell::Unknown, Cell::Unknown, Cell::Flag][..])
+        );
+        assert_eq!(grid.row(3), None);
+    }
+
+    #[test]
+    fn columns_1() {
+        let grid = generate_grid();
+
+        let column: Vec<&Cell> = grid.column(1).unwrap().collect();
+        assert_eq!(column, vec![&Cell::Unknown, &Cell::Bomb, &Cell::Unknown]);
+
+        let column: Vec<&Cell> = grid.column(2).unwrap().collect();
+        assert_eq!(column, vec![&Cell::Number(5), &Cell::Unknown, &Cell::Flag]);
+
+        assert!(grid.column(3).is_none());
+    }
+
     #[test]
     fn from_1() {
         let cells = generate_cells();
@@ -454,6 +670,105 @@ mod tests {
         assert_eq!(grid, grid_from_str)
     }
 
+    #[test]
+    fn from_str_2() {
+        let grid = Grid::from_str(
+            "?  ?  F
+?  X  ?
+0  ?  5",
+        );
+
+        assert_eq!(
+            grid,
+            Err(Error::Parse {
+                line: 2,
+                column: 4,
+                message: "unknown character: `X`".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn from_str_3() {
+        let grid = Grid::from_str(
+            "?  ?  F
+?  B
+0  ?  5",
+        );
+
+        assert!(matches!(grid, Err(Error::Parse { line: 2, .. })));
+    }
+
+    #[test]
+    fn from_str_4() {
+        // larger neighbourhoods have numbers above 8
+        let grid = Grid::from_str("12  ?\n0  24").unwrap();
+
+        assert_eq!(grid.get_cell(0, 1), Some(Cell::Number(12)));
+        assert_eq!(grid.get_cell(1, 0), Some(Cell::Number(24)));
+        assert!(Grid::from_str("256  ?").is_err());
+    }
+
+    #[test]
+    fn from_str_5() {
+        let grid = Grid::from_hex_str(
+            "?  ?  F
+ ?  B  ?
+0  ?  5",
+        )
+        .unwrap();
+        assert_eq!(grid.neighbourhood, Neighbourhood::Hex);
+        assert_eq!(grid.get_cell(1, 1), Some(Cell::Bomb));
+
+        let neighbours: Vec<(u16, u16)> = grid.neighbours(1, 1).collect();
+        assert_eq!(
+            neighbours,
+            vec![(1, 2), (2, 2), (0, 1), (2, 1), (1, 0), (2, 0)]
+        );
+        let neighbours: Vec<(u16, u16)> = grid.neighbours(1, 0).collect();
+        assert_eq!(neighbours, vec![(0, 1), (1, 1), (0, 0), (2, 0)]);
+    }
+
+    #[test]
+    fn from_str_6() {
+        // indentation never makes a square grid hex, even when it looks staggered
+        for input in ["?  ?  F\n    ?  B  ?", " ?  ?  F\n?  B  ?\n ?  ?  ?"] {
+            let grid = Grid::from_str(input).unwrap();
+            assert_eq!(grid.neighbourhood, Neighbourhood::Moore);
+            assert_eq!(grid.get_cell(0, 1), Some(Cell::Unknown));
+        }
+    }
+
+    #[test]
+    fn to_string_1() {
+        let grid = Grid::new(3, 2, Cell::Unknown).with_neighbourhood(Neighbourhood::Hex);
+
+        let rendered = grid.to_string(None);
+        let lines: Vec<&str> = rendered.lines().collect();
+        assert!(lines[0].starts_with("  \u{1b}"));
+        assert!(lines[1].starts_with(" \u{1b}"));
+        assert_eq!(lines[0].len(), lines[1].len());
+        assert!(lines[0].ends_with(" | 1"));
+    }
+
+    #[test]
+    fn to_string_2() {
+        // numbers of more than one digit widen every column
+        let grid = Grid::from_str("12  ?\n0  3").unwrap();
+
+        let rendered = grid.to_string(None);
+        let mut plain = String::new();
+        let mut chars = rendered.chars();
+        while let Some(c) = chars.next() {
+            if c == '\u{1b}' {
+                chars.by_ref().find(|&c| c == 'm');
+            } else {
+                plain.push(c);
+            }
+        }
+        assert_eq!(plain, " 12   ?  | 1\n  0   3  | 0\n ---------\n  0   1 \n");
+    }
+
     #[test]
     fn get_cell_1() {
         let grid = generate_grid();
@@ -504,7 +819,7 @@ mod tests {
         let mut grid = generate_grid();
 
         let res = grid.set_cell(4, 0, Cell::Bomb);
-        assert!(res.is_err());
+        assert_eq!(res, Err(Error::OutOfBounds { x: 4, y: 0 }));
     }
 
     #[test]
@@ -512,9 +827,9 @@ mod tests {
         let grid = generate_grid();
         println!("{}", grid);
         let adj_cells = grid.adj_cells(1, 1, None).unwrap();
-        let adj_cells: HashSet<&(u8, u8), RandomState> = HashSet::from_iter(adj_cells.iter());
+        let adj_cells: HashSet<&(u16, u16), RandomState> = HashSet::from_iter(adj_cells.iter());
 
-        let expected_adj_cells: HashSet<&(u8, u8)> = HashSet::from_iter(
+        let expected_adj_cells: HashSet<&(u16, u16)> = HashSet::from_iter(
             [
                 (0, 0),
                 (1, 0),
@@ -531,6 +846,64 @@ mod tests {
         assert_eq!(adj_cells, expected_adj_cells);
     }
 
+    #[test]
+    fn neighbours_1() {
+        let grid = generate_grid();
+
+        let neighbours: Vec<(u16, u16)> = grid.neighbours(0, 0).collect();
+        assert_eq!(neighbours, vec![(0, 1), (1, 1), (1, 0)]);
+
+        assert_eq!(grid.neighbours(1, 1).count(), 8);
+        assert_eq!(grid.neighbours(2, 1).count(), 5);
+    }
+
+    #[test]
+    fn neighbours_2() {
+        let grid = generate_grid().with_topology(Topology::Torus);
+
+        let neighbours: HashSet<(u16, u16)> = grid.neighbours(0, 0).collect();
+        assert_eq!(neighbours.len(), 8);
+        assert!(neighbours.contains(&(2, 2)));
+        assert!(!neighbours.contains(&(0, 0)));
+
+        let grid = grid.with_topology(Topology::Cylinder);
+        let neighbours: HashSet<(u16, u16)> = grid.neighbours(0, 0).collect();
+        assert_eq!(
+            neighbours,
+            HashSet::from([(0, 1), (1, 1), (2, 1), (1, 0), (2, 0)])
+        );
+    }
+
+    #[test]
+    fn neighbours_3() {
+        // narrow grids wrap onto the same cells from both sides
+        let grid = Grid::new(2, 2, Cell::Unknown).with_topology(Topology::Torus);
+        let neighbours: Vec<(u16, u16)> = grid.neighbours(0, 0).collect();
+        assert_eq!(neighbours, vec![(1, 1), (0, 1), (1, 0)]);
+
+        let grid = Grid::new(1, 1, Cell::Unknown).with_topology(Topology::Torus);
+        assert_eq!(grid.neighbours(0, 0).count(), 0);
+    }
+
+    #[test]
+    fn neighbours_4() {
+        let grid = Grid::new(5, 5, Cell::Unknown).with_neighbourhood(Neighbourhood::Knight);
+        assert_eq!(
+            grid.neighbours(0, 0).collect::<Vec<_>>(),
+            vec![(1, 2), (2, 1)]
+        );
+        assert_eq!(grid.neighbours(2, 2).count(), 8);
+
+        // on a 3x3 torus every knight move lands on one of 4 cells, each reached twice
+        let grid = Grid::new(3, 3, Cell::Unknown)
+            .with_neighbourhood(Neighbourhood::Knight)
+            .with_topology(Topology::Torus);
+        assert_eq!(
+            grid.neighbours(0, 0).collect::<Vec<_>>(),
+            vec![(2, 2), (1, 2), (1, 1), (2, 1)]
+        );
+    }
+
     #[test]
     fn adj_cells_2() {
         let grid = generate_grid();
@@ -538,9 +911,9 @@ mod tests {
         let adj_cells = grid
             .adj_cells(1, 1, Some(HashSet::from([Cell::Unknown])))
             .unwrap();
-        let adj_cells: HashSet<&(u8, u8), RandomState> = HashSet::from_iter(adj_cells.iter());
+        let adj_cells: HashSet<&(u16, u16), RandomState> = HashSet::from_iter(adj_cells.iter());
 
-        let expected_adj_cells: HashSet<&(u8, u8)> =
+        let expected_adj_cells: HashSet<&(u16, u16)> =
             HashSet::from_iter([(1, 0), (0, 1), (2, 1), (0, 2), (1, 2)].iter());
 
         assert_eq!(adj_cells, expected_adj_cells);
@@ -551,9 +924,9 @@ mod tests {
         let grid = generate_grid();
         println!("{}", grid);
         let adj_cells = grid.adj_number(1, 1).unwrap();
-        let adj_cells: HashSet<&(u8, u8), RandomState> = HashSet::from_iter(adj_cells.iter());
+        let adj_cells: HashSet<&(u16, u16), RandomState> = HashSet::from_iter(adj_cells.iter());
 
-        let expected_adj_cells: HashSet<&(u8, u8)> = HashSet::from_iter([(2, 0)].iter());
+        let expected_adj_cells: HashSet<&(u16, u16)> = HashSet::from_iter([(2, 0)].iter());
 
         assert_eq!(adj_cells, expected_adj_cells);
     }
@@ -563,9 +936,9 @@ mod tests {
         let grid = generate_grid();
         println!("{}", grid);
         let adj_cells = grid.adj_flags(1, 1).unwrap();
-        let adj_cells: HashSet<&(u8, u8), RandomState> = HashSet::from_iter(adj_cells.iter());
+        let adj_cells: HashSet<&(u16, u16), RandomState> = HashSet::from_iter(adj_cells.iter());
 
-        let expected_adj_cells: HashSet<&(u8, u8)> = HashSet::from_iter([(2, 2)].iter());
+        let expected_adj_cells: HashSet<&(u16, u16)> = HashSet::from_iter([(2, 2)].iter());
 
         assert_eq!(adj_cells, expected_adj_cells);
     }
@@ -575,9 +948,9 @@ mod tests {
         let grid = generate_grid();
         println!("{}", grid);
         let adj_cells = grid.adj_flags(0, 1).unwrap();
-        let adj_cells: HashSet<&(u8, u8), RandomState> = HashSet::from_iter(adj_cells.iter());
+        let adj_cells: HashSet<&(u16, u16), RandomState> = HashSet::from_iter(adj_cells.iter());
 
-        let expected_adj_cells: HashSet<&(u8, u8)> = HashSet::from_iter([].iter());
+        let expected_adj_cells: HashSet<&(u16, u16)> = HashSet::from_iter([].iter());
 
         assert_eq!(adj_cells, expected_adj_cells);
     }
@@ -587,9 +960,9 @@ mod tests {
         let grid = generate_grid();
         println!("{}", grid);
         let adj_cells = grid.adj_bombs(0, 2).unwrap();
-        let adj_cells: HashSet<&(u8, u8), RandomState> = HashSet::from_iter(adj_cells.iter());
+        let adj_cells: HashSet<&(u16, u16), RandomState> = HashSet::from_iter(adj_cells.iter());
 
-        let expected_adj_cells: HashSet<&(u8, u8)> = HashSet::from_iter([(1, 1)].iter());
+        let expected_adj_cells: HashSet<&(u16, u16)> = HashSet::from_iter([(1, 1)].iter());
 
         assert_eq!(adj_cells, expected_adj_cells);
     }
@@ -0,0 +1,98 @@
+//! Minesweeper solver used by Multi-Sweeper.
+//!
+//! `GameBoard` holds the player's view of a board and a `RevealOracle` that answers reveals
+//! (by default the solved grid), the solve steps (`simple_solve_step`, `pair_solve_step`,
+//! `linear_solve_step`, `permute_solve_step`, `sat_solve_step`, `solve`) and
+//! `bomb_probabilities` are implemented on it, and `strategy` combines them into full games.
+//! `deduce` works from nothing but a visible grid and the bomb count. `ChunkedBoard` is an
+//! endless board generated chunk by chunk from a world seed.
+//!
+//! ```
+//! use solver::{FirstClick, GameBoard};
+//!
+//! let mut board = GameBoard::new_first_click_seeded(9, 9, 10, (4, 4), FirstClick::Opening, 7)?;
+//! board.reveal(4, 4)?;
+//! let solved = board.solve()?;
+//! # let _ = solved;
+//! # Ok::<(), solver::Error>(())
+//! ```
+
+#[cfg(test)]
+#[macro_use]
+extern crate time_test;
+
+pub mod board;
+pub mod chunk;
+pub mod colour;
+pub mod error;
+pub mod explain;
+#[cfg(test)]
+mod fixtures;
+pub mod grid;
+pub mod neighbourhood;
+pub mod oracle;
+pub mod probability;
+pub mod sat;
+pub mod solve;
+pub mod strategy;
+mod utils;
+
+pub use board::{FirstClick, GameBoard, NoOracle, RevealOracle};
+pub use chunk::{ChunkGenerator, ChunkedBoard};
+pub use error::Error;
+pub use explain::{Explanation, Rule};
+pub use grid::{Grid, Topology};
+pub use neighbourhood::Neighbourhood;
+pub use oracle::{ProcessOracle, ReplayOracle};
+pub use sat::{Cnf, FrontierCnf};
+pub use solve::{ComponentTally, Deductions, deduce};
+pub use strategy::{
+    BasicStrategy, GameResult, Guess, GuessStrategy, LinearStrategy, PairStrategy, PermuteStrategy,
+    SatStrategy, Step, Strategy, StrategyConfig, StrategyResult, Technique, registry,
+    strategy_by_name,
+};
+
+use colour::Coloured;
+
+#[derive(Clone, Copy, Hash, PartialEq, Eq, Debug)]
+pub enum Cell {
+    Number(u8),
+    Flag,
+    Bomb,
+    Unknown,
+    Asterix,
+}
+
+impl Cell {
+    /// Parses a single cell in the text format used by `Grid::from_str`.
+    pub fn from_token(token: &str) -> Option<Cell> {
+        match token {
+            "B" => Some(Cell::Bomb),
+            "F" => Some(Cell::Flag),
+            "?" => Some(Cell::Unknown),
+            "*" => Some(Cell::Asterix),
+            // numbers above 8 only come up with neighbourhoods larger than the classic one
+            _ if token.bytes().all(|b| b.is_ascii_digit()) => token.parse().ok().map(Cell::Number),
+            _ => None,
+        }
+    }
+}
+
+impl Coloured for Cell {
+    fn to_coloured(&self, background: Option<(u8, u8, u8)>) -> String {
+        match self {
+            Cell::Number(num) => num.to_string().to_coloured(background),
+            Cell::Bomb => String::from("B").to_coloured(background),
+            Cell::Flag => String::from("F").to_coloured(background),
+            Cell::Unknown => String::from("?").to_coloured(background),
+            Cell::Asterix => String::from("*").to_coloured(background),
+        }
+    }
+
+    fn width(&self) -> usize {
+        match self {
+            Cell::Number(num) => num.to_string().len(),
+            _ => 1,
+        }
+    }
+}
@@ -1,89 +1,136 @@
-#[macro_use]
-extern crate time_test;
-
-use board::GameBoard;
-use colour::Coloured;
+use solver::{Cell, FirstClick, GameBoard, Grid, Strategy, Technique, registry, strategy_by_name};
+use std::error::Error;
 use std::time::Instant;
-use strategy::strategy_simple_permute;
 
-mod board;
-mod colour;
-mod grid;
-mod solve;
-mod strategy;
-mod utils;
+const USAGE: &str =
+    "usage: solver [SEED] [--strategy NAME]... [--set OPTION=VALUE]... [--json PATH] [--list]";
 
-#[derive(Clone, Copy, Hash, PartialEq, Eq, Debug)]
-enum Cell {
-    Number(u8),
-    Flag,
-    Bomb,
-    Unknown,
-    Asterix,
-}
+fn main() -> Result<(), Box<dyn Error>> {
+    let mut seed: Option<u64> = None;
+    let mut names: Vec<String> = Vec::new();
+    let mut options: Vec<(String, String)> = Vec::new();
+    let mut json_path: Option<String> = None;
 
-impl Coloured for Cell {
-    fn to_coloured(&self, background: Option<(u8, u8, u8)>) -> String {
-        match self {
-            Cell::Number(num) => num.to_string().to_coloured(background),
-            Cell::Bomb => String::from("B").to_coloured(background),
-            Cell::Flag => String::from("F").to_coloured(background),
-            Cell::Unknown => String::from("?").to_coloured(background),
-            Cell::Asterix => String::from("*").to_coloured(background),
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--strategy" | "-s" => {
+                let name = args
+                    .next()
+                    .ok_or(format!("missing strategy name\n{}", USAGE))?;
+                names.push(name);
+            }
+            "--set" => {
+                let option = args.next().ok_or(format!("missing option\n{}", USAGE))?;
+                let (option, value) = option
+                    .split_once('=')
+                    .ok_or(format!("expected OPTION=VALUE, got `{}`", option))?;
+                options.push((option.to_string(), value.to_string()));
+            }
+            // write every strategy's step summary to a file for later analysis
+            "--json" => {
+                json_path = Some(args.next().ok_or(format!("missing path\n{}", USAGE))?);
+            }
+            "--list" => {
+                for strategy in registry::<Grid<Cell>>() {
+                    println!("{:<10}{}", strategy.name(), strategy.description());
+                }
+                return Ok(());
+            }
+            "--help" | "-h" => {
+                println!("{}", USAGE);
+                return Ok(());
+            }
+            // pass a seed to replay a previous board
+            _ => {
+                seed = Some(
+                    arg.parse()
+                        .map_err(|_| format!("invalid seed: `{}`\n{}", arg, USAGE))?,
+                )
+            }
         }
     }
-}
 
-fn get_starting_cells(board: GameBoard) -> Result<Vec<(u8, u8)>, String> {
-    let mut starting_cells: Vec<(usize, (u8, u8))> = Vec::new();
-    let mut temp_board = board.clone();
-    for cell in temp_board.solved_grid.get_iter() {
-        let (x, y) = cell.pos;
-        if cell.val == Cell::Number(0) {
-            if let Some(Cell::Unknown) = temp_board.grid.get_cell(x.into(), y.into()) {
-                let pre_zeros = temp_board
-                    .grid
-                    .get_iter()
-                    .filter(|cell| cell.val == Cell::Number(0))
-                    .count();
-                temp_board.flood_fill(x.into(), y.into())?;
-                let post_zeros = temp_board
-                    .grid
-                    .get_iter()
-                    .filter(|cell| cell.val == Cell::Number(0))
-                    .count();
-                starting_cells.push((post_zeros - pre_zeros, (x, y)));
-            }
+    // with no strategy given, compare every registered strategy
+    let mut strategies: Vec<Box<dyn Strategy<Grid<Cell>>>> = if names.is_empty() {
+        registry()
+    } else {
+        names
+            .iter()
+            .map(|name| strategy_by_name(name))
+            .collect::<Result<_, _>>()?
+    };
+    for strategy in strategies.iter_mut() {
+        for (option, value) in options.iter() {
+            strategy.configure(option, value)?;
         }
     }
 
-    starting_cells.sort_by(|a, b| b.0.cmp(&a.0));
+    let seed = seed.unwrap_or_else(rand::random);
+    println!("seed: {}", seed);
 
-    let sorted_starting_cells = starting_cells.iter().map(|x| x.1).collect();
+    // bombs are placed after the first click, which is always guaranteed to open up an area
+    let first_click = (8, 8);
+    // let board: GameBoard =
+    //     GameBoard::new_first_click_seeded(9, 9, 10, (4, 4), FirstClick::Opening, seed)?;
+    let board: GameBoard =
+        GameBoard::new_first_click_seeded(16, 16, 40, first_click, FirstClick::Opening, seed)?;
+    // let board: GameBoard =
+    //     GameBoard::new_first_click_seeded(30, 16, 99, (15, 8), FirstClick::Opening, seed)?;
 
-    Ok(sorted_starting_cells)
-}
+    let mut results = Vec::new();
+    for strategy in strategies.iter() {
+        println!(
+            "= strategy: {} ===============================",
+            strategy.name()
+        );
 
-fn main() -> Result<(), String> {
-    // let board: GameBoard = GameBoard::new(9, 9, 10).unwrap();
-    let board: GameBoard = GameBoard::new(16, 16, 40).unwrap();
-    // let board: GameBoard = GameBoard::new(30, 16, 99).unwrap();
-    // let board: GameBoard = GameBoard::new(16, 16, 10).unwrap();
+        let start_time = Instant::now();
+        // one strategy failing shouldn't stop the others from being compared
+        let result = match strategy.run(board.clone(), first_click) {
+            Ok(result) => result,
+            Err(err) => {
+                println!("failed: {}", err);
+                continue;
+            }
+        };
+        let elapsed = start_time.elapsed().as_millis();
 
-    // determine all possible starting cells
-    let starting_cells = get_starting_cells(board.clone())?;
+        println!("{:?} in: {}ms", result.result, elapsed);
+        results.push((strategy.name(), result, elapsed));
+    }
 
-    let start_solve_time = Instant::now();
-    let mut step_summary: Option<Vec<Vec<&str>>> = None;
-    for starting_cell in &starting_cells[0..1] {
-        step_summary = strategy_simple_permute(board.clone(), starting_cell.to_owned())?;
+    println!("= results ====================================");
+    for (name, result, elapsed) in results.iter() {
+        let count = |technique| {
+            result
+                .step_summary
+                .iter()
+                .filter(|step| step.technique == Some(technique))
+                .count()
+        };
+        println!(
+            "{:<10}{:<8}{:>4} steps ({} basic, {} pair, {} linear, {} permute, {} sat, {} guess) {:>6}ms",
+            name,
+            format!("{:?}", result.result),
+            result.step_summary.len(),
+            count(Technique::Basic),
+            count(Technique::Pair),
+            count(Technique::Linear),
+            count(Technique::Permute),
+            count(Technique::Sat),
+            count(Technique::Guess),
+            elapsed
+        );
     }
 
-    if step_summary.is_some() {
-        println!("Solved in: {}ms", start_solve_time.elapsed().as_millis());
-        println!("step summary: {:?}", step_summary);
-    } else {
-        println!("could not solve");
+    if let Some(path) = json_path {
+        let json: Vec<_> = results
+            .iter()
+            .map(|(name, result, _)| serde_json::json!({ "seed": seed, "strategy": name, "result": result }))
+            .collect();
+        std::fs::write(&path, serde_json::to_string_pretty(&json)?)?;
+        println!("step summaries written to {}", path);
     }
 
     Ok(())
@@ -0,0 +1,203 @@
+use std::{collections::HashSet, sync::Arc};
+
+use crate::error::Error;
+
+// offsets of the 8 neighbours of a cell, top row first
+const MOORE: [(i32, i32); 8] = [
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+    (-1, 0),
+    (1, 0),
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+];
+
+const VON_NEUMANN: [(i32, i32); 4] = [(0, 1), (-1, 0), (1, 0), (0, -1)];
+
+const KNIGHT: [(i32, i32); 8] = [
+    (-1, 2),
+    (1, 2),
+    (-2, 1),
+    (2, 1),
+    (-2, -1),
+    (2, -1),
+    (-1, -2),
+    (1, -2),
+];
+
+// the 6 neighbours of a hex cell, where odd rows are shifted half a cell to the right of even rows
+const HEX_EVEN_ROW: [(i32, i32); 6] = [(-1, 1), (0, 1), (-1, 0), (1, 0), (-1, -1), (0, -1)];
+const HEX_ODD_ROW: [(i32, i32); 6] = [(0, 1), (1, 1), (-1, 0), (1, 0), (0, -1), (1, -1)];
+
+// every cell of the 5x5 square around a cell, top row first
+const RADIUS_2: [(i32, i32); 24] = [
+    (-2, 2),
+    (-1, 2),
+    (0, 2),
+    (1, 2),
+    (2, 2),
+    (-2, 1),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+    (2, 1),
+    (-2, 0),
+    (-1, 0),
+    (1, 0),
+    (2, 0),
+    (-2, -1),
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (2, -1),
+    (-2, -2),
+    (-1, -2),
+    (0, -2),
+    (1, -2),
+    (2, -2),
+];
+
+/// Which cells count as the neighbours of a cell, ie. which cells its number counts bombs in.
+#[derive(Clone, Debug, Default, Hash, PartialEq, Eq)]
+pub enum Neighbourhood {
+    /// the 8 surrounding cells of classic minesweeper
+    #[default]
+    Moore,
+    /// the 4 orthogonally adjacent cells
+    VonNeumann,
+    /// the 8 cells a chess knight can move to
+    Knight,
+    /// the 24 cells of the 5x5 square around the cell
+    Radius2,
+    /// the 6 cells around a hexagonal cell, with rows in offset coordinates: every odd row
+    /// (counting the bottom row as row 0) sits half a cell to the right of the rows around it. A
+    /// torus needs an even height so the rows on either side of the join stay offset
+    Hex,
+    /// a user-defined set of offsets, see `Neighbourhood::custom`
+    Custom(Deltas),
+}
+
+/// Offsets of a custom neighbourhood, checked by `Neighbourhood::custom`.
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub struct Deltas(Arc<[(i32, i32)]>);
+
+impl Neighbourhood {
+    /// A neighbourhood of the given (dx, dy) offsets. The solver relies on neighbours being
+    /// mutual, so every offset must come with its opposite, and a cell is never its own neighbour.
+    pub fn custom(deltas: &[(i32, i32)]) -> Result<Self, Error> {
+        let max = u32::from(u16::MAX);
+        let unique: HashSet<(i32, i32)> = deltas.iter().copied().collect();
+
+        if unique.len() != deltas.len() {
+            return Err(Error::Config(
+                "neighbourhood lists an offset twice".to_string(),
+            ));
+        }
+        if deltas.len() > u8::MAX.into() {
+            return Err(Error::Config(format!(
+                "neighbourhood has {} offsets, at most 255 are supported",
+                deltas.len()
+            )));
+        }
+        // checked first so negating an offset below can't overflow
+        for &(dx, dy) in deltas {
+            if (dx, dy) == (0, 0) || dx.unsigned_abs() > max || dy.unsigned_abs() > max {
+                return Err(Error::Config(format!(
+                    "invalid neighbourhood offset ({}, {})",
+                    dx, dy
+                )));
+            }
+        }
+        for &(dx, dy) in deltas {
+            if !unique.contains(&(-dx, -dy)) {
+                return Err(Error::Config(format!(
+                    "neighbourhood offset ({}, {}) has no opposite ({}, {})",
+                    dx, dy, -dx, -dy
+                )));
+            }
+        }
+
+        Ok(Neighbourhood::Custom(Deltas(deltas.into())))
+    }
+
+    /// The (dx, dy) offsets of the neighbours of a cell in row `y`, which only matters for `Hex`.
+    pub fn deltas(&self, y: i32) -> &[(i32, i32)] {
+        match self {
+            Neighbourhood::Moore => &MOORE,
+            Neighbourhood::VonNeumann => &VON_NEUMANN,
+            Neighbourhood::Knight => &KNIGHT,
+            Neighbourhood::Radius2 => &RADIUS_2,
+            Neighbourhood::Hex if y.rem_euclid(2) == 0 => &HEX_EVEN_ROW,
+            Neighbourhood::Hex => &HEX_ODD_ROW,
+            Neighbourhood::Custom(Deltas(deltas)) => deltas,
+        }
+    }
+
+    /// How far away (in either direction) the furthest neighbour is.
+    pub fn reach(&self) -> u16 {
+        self.deltas(0)
+            .iter()
+            .chain(self.deltas(1))
+            .map(|&(dx, dy)| dx.unsigned_abs().max(dy.unsigned_abs()) as u16)
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builtins_1() {
+        for neighbourhood in [
+            Neighbourhood::Moore,
+            Neighbourhood::VonNeumann,
+            Neighbourhood::Knight,
+            Neighbourhood::Radius2,
+        ] {
+            let deltas = neighbourhood.deltas(0);
+            assert_eq!(
+                Neighbourhood::custom(deltas).unwrap().deltas(0),
+                deltas,
+                "{:?}",
+                neighbourhood
+            );
+        }
+
+        assert_eq!(Neighbourhood::Moore.reach(), 1);
+        assert_eq!(Neighbourhood::Knight.reach(), 2);
+        assert_eq!(Neighbourhood::Radius2.reach(), 2);
+        assert_eq!(Neighbourhood::Hex.reach(), 1);
+    }
+
+    #[test]
+    fn hex_1() {
+        // every hex neighbour of a cell has that cell as a neighbour in turn
+        for y in 0..2 {
+            for &(dx, dy) in Neighbourhood::Hex.deltas(y) {
+                assert!(Neighbourhood::Hex.deltas(y + dy).contains(&(-dx, -dy)));
+            }
+        }
+    }
+
+    #[test]
+    fn custom_1() {
+        assert!(Neighbourhood::custom(&[(3, 0), (-3, 0)]).is_ok());
+
+        assert!(Neighbourhood::custom(&[(0, 0)]).is_err());
+        assert!(Neighbourhood::custom(&[(1, 0)]).is_err());
+        assert!(Neighbourhood::custom(&[(1, 0), (-1, 0), (1, 0)]).is_err());
+
+        assert!(matches!(
+            Neighbourhood::custom(&[(i32::MIN, 0)]),
+            Err(Error::Config(_))
+        ));
+        assert!(matches!(
+            Neighbourhood::custom(&[(0, i32::MIN), (0, i32::MAX)]),
+            Err(Error::Config(_))
+        ));
+    }
+}
@@ -0,0 +1,215 @@
+use std::{
+    collections::HashMap,
+    fs,
+    io::{BufRead, BufReader, Write},
+    path::Path,
+    process::{Child, ChildStdin, ChildStdout, Command, Stdio},
+    str::FromStr,
+};
+
+use crate::{Cell, board::RevealOracle, error::Error};
+
+fn parse_revealed(token: &str) -> Option<Cell> {
+    match Cell::from_token(token) {
+        Some(cell @ (Cell::Number(_) | Cell::Bomb)) => Some(cell),
+        _ => None,
+    }
+}
+
+/// Answers reveals from a recorded game. A replay file has one revealed cell per line, written as
+/// `x y cell` (eg. `3 4 2` or `0 7 B`), using the same coordinates as `Grid`. Blank lines and lines
+/// starting with `#` are ignored.
+#[derive(Clone, Debug, Default)]
+pub struct ReplayOracle {
+    cells: HashMap<(i32, i32), Cell>,
+}
+
+impl ReplayOracle {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let contents = fs::read_to_string(path.as_ref()).map_err(|err| {
+            Error::Oracle(format!(
+                "could not read replay {}: {}",
+                path.as_ref().display(),
+                err
+            ))
+        })?;
+
+        ReplayOracle::from_str(&contents)
+    }
+}
+
+impl FromStr for ReplayOracle {
+    type Err = Error;
+
+    fn from_str(input_str: &str) -> Result<Self, Error> {
+        let mut cells = HashMap::new();
+
+        for (line, row) in input_str.lines().enumerate() {
+            let row = row.trim();
+            if row.is_empty() || row.starts_with('#') {
+                continue;
+            }
+
+            let parse_error = |message: &str| Error::Parse {
+                line: line + 1,
+                column: 1,
+                message: message.to_string(),
+            };
+
+            let tokens: Vec<&str> = row.split_whitespace().collect();
+            let [x, y, cell] = tokens[..] else {
+                return Err(parse_error("expected `x y cell`"));
+            };
+
+            let x = x.parse().map_err(|_| parse_error("invalid x coordinate"))?;
+            let y = y.parse().map_err(|_| parse_error("invalid y coordinate"))?;
+            let cell = parse_revealed(cell).ok_or(parse_error("expected a number or `B`"))?;
+
+            cells.insert((x, y), cell);
+        }
+
+        Ok(ReplayOracle { cells })
+    }
+}
+
+impl RevealOracle for ReplayOracle {
+    fn reveal(&mut self, x: i32, y: i32) -> Result<Cell, Error> {
+        self.cells
+            .get(&(x, y))
+            .copied()
+            .ok_or(Error::Oracle(format!(
+                "({}, {}) is not in the replay",
+                x, y
+            )))
+    }
+}
+
+/// Plays against an external game running as a child process, speaking a line-based protocol over
+/// its stdin/stdout. For every reveal the solver writes `reveal x y` and the game answers with the
+/// revealed cell (`0`-`8` or `B`), or `error <message>`.
+pub struct ProcessOracle {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+}
+
+impl ProcessOracle {
+    pub fn spawn(mut command: Command) -> Result<Self, Error> {
+        let mut child = command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(|err| Error::Oracle(format!("could not start game process: {}", err)))?;
+
+        let stdin = child
+            .stdin
+            .take()
+            .ok_or(Error::Oracle("game process has no stdin".to_string()))?;
+        let stdout = child
+            .stdout
+            .take()
+            .ok_or(Error::Oracle("game process has no stdout".to_string()))?;
+
+        Ok(ProcessOracle {
+            child,
+            stdin,
+            stdout: BufReader::new(stdout),
+        })
+    }
+}
+
+impl RevealOracle for ProcessOracle {
+    fn reveal(&mut self, x: i32, y: i32) -> Result<Cell, Error> {
+        let io_error = |err: std::io::Error| Error::Oracle(format!("game process: {}", err));
+
+        writeln!(self.stdin, "reveal {} {}", x, y).map_err(io_error)?;
+        self.stdin.flush().map_err(io_error)?;
+
+        let mut response = String::new();
+        if self.stdout.read_line(&mut response).map_err(io_error)? == 0 {
+            return Err(Error::Oracle("game process closed its output".to_string()));
+        }
+
+        let response = response.trim();
+        if let Some(message) = response.strip_prefix("error") {
+            return Err(Error::Oracle(format!(
+                "game process could not reveal ({}, {}): {}",
+                x,
+                y,
+                message.trim()
+            )));
+        }
+
+        parse_revealed(response).ok_or(Error::Oracle(format!(
+            "unexpected response from game process: `{}`",
+            response
+        )))
+    }
+}
+
+impl Drop for ProcessOracle {
+    fn drop(&mut self) {
+        // the game may already have exited, in which case there is nothing to clean up
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{board::GameBoard, grid::Grid};
+
+    fn get_replay_str() -> &'static str {
+        "# 3x3 board with a bomb in the top left
+        0 0 0
+        1 0 0
+        2 0 0
+        0 1 1
+        1 1 1
+        2 1 0
+        0 2 B
+        1 2 1
+        2 2 0"
+    }
+
+    #[test]
+    fn replay_1() {
+        let replay = ReplayOracle::from_str(get_replay_str()).unwrap();
+        let mut board = GameBoard::from_view(Grid::new(3, 3, Cell::Unknown), 1, replay).unwrap();
+
+        board.reveal(0, 0).unwrap();
+        assert_eq!(board.grid.get_cell(2, 2), Some(Cell::Number(0)));
+        assert_eq!(board.deductions().unwrap().bombs, vec![(0, 2)]);
+        assert!(board.is_solved().unwrap());
+    }
+
+    #[test]
+    fn replay_2() {
+        let replay = ReplayOracle::from_str("0 0 X");
+        assert!(matches!(replay, Err(Error::Parse { line: 1, .. })));
+
+        let mut replay = ReplayOracle::from_str("0 0 1").unwrap();
+        assert!(matches!(replay.reveal(1, 1), Err(Error::Oracle(_))));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn process_1() {
+        // a "game" with a single bomb at (0, 0) on a 2x1 board
+        let mut command = Command::new("sh");
+        command.arg("-c").arg(
+            "while read cmd x y; do
+                if [ \"$x\" = 0 ]; then echo B; else echo 1; fi
+            done",
+        );
+
+        let oracle = ProcessOracle::spawn(command).unwrap();
+        let mut board = GameBoard::from_view(Grid::new(2, 1, Cell::Unknown), 1, oracle).unwrap();
+
+        assert!(board.reveal(1, 0).unwrap());
+        assert_eq!(board.grid.get_cell(1, 0), Some(Cell::Number(1)));
+        assert_eq!(board.deductions().unwrap().bombs, vec![(0, 0)]);
+        assert!(board.is_solved().unwrap());
+    }
+}
@@ -0,0 +1,358 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    Cell,
+    board::{GameBoard, RevealOracle},
+    error::Error,
+    grid::Grid,
+    solve::ComponentTally,
+};
+
+// ways[s] is the relative number of ways the given components can hold exactly s bombs
+fn weighted_sums<'a>(tallies: impl Iterator<Item = &'a ComponentTally>) -> Vec<f64> {
+    let mut ways = vec![1.0];
+
+    for tally in tallies {
+        // only ratios matter, so normalise each component to keep the products in range
+        let total = tally.total_solutions() as f64;
+        let mut next = vec![0.0; ways.len() + tally.solutions.len() - 1];
+        for (s, w) in ways.iter().enumerate() {
+            for (k, &n) in tally.solutions.iter().enumerate() {
+                next[s + k] += w * n as f64 / total;
+            }
+        }
+        ways = next;
+    }
+
+    ways
+}
+
+/// Relative number of ways to place r bombs among `interior` cells, for r in `min_r..=max_r`.
+/// The binomial coefficients are built up in log space as they overflow quickly on big boards.
+fn interior_weights(interior: usize, min_r: usize, max_r: usize) -> Vec<f64> {
+    let mut log_weights = vec![0.0];
+    for r in min_r..max_r {
+        let prev = log_weights[log_weights.len() - 1];
+        log_weights.push(prev + ((interior - r) as f64 / (r + 1) as f64).ln());
+    }
+
+    let max = log_weights
+        .iter()
+        .cloned()
+        .fold(f64::NEG_INFINITY, f64::max);
+    log_weights.iter().map(|w| (w - max).exp()).collect()
+}
+
+impl<O: RevealOracle> GameBoard<O> {
+    // a guess for a cell of a component too complex to count: the average share of their unknown
+    // neighbours that the numbers around it still need to be bombs
+    fn local_density(&self, densities: &HashMap<(u16, u16), f64>, x: u16, y: u16) -> f64 {
+        let around: Vec<f64> = self
+            .grid
+            .neighbours(x, y)
+            .filter_map(|number| densities.get(&number).copied())
+            .collect();
+
+        around.iter().sum::<f64>() / around.len().max(1) as f64
+    }
+
+    // the share of its unknown neighbours each frontier number still needs to be bombs
+    fn frontier_densities(&self) -> Result<HashMap<(u16, u16), f64>, Error> {
+        Ok(self
+            .frontier_numbers()?
+            .into_iter()
+            .map(|number| {
+                let density = f64::from(number.remaining) / number.unknown.len() as f64;
+                (number.pos, density)
+            })
+            .collect())
+    }
+
+    /// Rough bomb probabilities for when `bomb_probabilities` cannot be worked out, eg. on a board
+    /// without a total bomb count. Frontier cells get the average density of the numbers around
+    /// them and every other unknown cell the average over the frontier. Revealed cells are 0 and
+    /// flagged cells are 1.
+    pub fn estimated_probabilities(&self) -> Result<Grid<f64>, Error> {
+        let mut probabilities = self.grid.new_like(0.0);
+        let densities = self.frontier_densities()?;
+
+        let potential_bombs = self.potential_bombs()?;
+        let mut total = 0.0;
+        for &(x, y) in &potential_bombs {
+            let probability = self.local_density(&densities, x, y);
+            total += probability;
+            probabilities.set_cell(x.into(), y.into(), probability)?;
+        }
+
+        // with nothing revealed every unknown cell is as likely as any other
+        let interior = if potential_bombs.is_empty() {
+            0.5
+        } else {
+            total / potential_bombs.len() as f64
+        };
+        let frontier: HashSet<(u16, u16)> = potential_bombs.into_iter().collect();
+        for cell in self.grid.get_iter() {
+            let (x, y) = cell.pos;
+            match cell.val {
+                Cell::Unknown if !frontier.contains(&cell.pos) => {
+                    probabilities.set_cell(x.into(), y.into(), interior)?
+                }
+                Cell::Flag => probabilities.set_cell(x.into(), y.into(), 1.0)?,
+                _ => (),
+            }
+        }
+
+        Ok(probabilities)
+    }
+
+    /// Probability of each cell being a bomb, over every arrangement of the remaining bombs that
+    /// agrees with the revealed numbers. Revealed cells are 0 and flagged cells are 1. Cells of
+    /// frontier components too complex to count only get an estimate from the numbers around
+    /// them, and their expected bombs are taken off the bomb count shared by the rest.
+    pub fn bomb_probabilities(&self) -> Result<Grid<f64>, Error> {
+        let mut probabilities = self.grid.new_like(0.0);
+
+        if self.num_bombs().is_none() {
+            return Err(Error::SolverLimit(
+                "bomb probabilities need the total bomb count".to_string(),
+            ));
+        }
+        let remaining: usize = self.remaining_bombs().ok_or(Error::InconsistentBoard(
+            "more flags than bombs".to_string(),
+        ))? as usize;
+
+        let potential_bombs = self.potential_bombs()?;
+        let mut components = Vec::new();
+        let mut tallies = Vec::new();
+        let mut skipped = Vec::new();
+        for component in self.frontier_components(&potential_bombs)? {
+            match self.tally_component(&component) {
+                Ok(tally) => {
+                    components.push(component);
+                    tallies.push(tally);
+                }
+                Err(Error::SolverLimit(_)) => skipped.push(component),
+                Err(err) => return Err(err),
+            }
+        }
+
+        if tallies.iter().any(|tally| tally.total_solutions() == 0) {
+            return Err(Error::InconsistentBoard(
+                "no valid bomb pattern".to_string(),
+            ));
+        }
+
+        let densities = self.frontier_densities()?;
+        let mut skipped_bombs = 0.0;
+        for &(x, y) in skipped.iter().flatten() {
+            let probability = self.local_density(&densities, x, y);
+            skipped_bombs += probability;
+            probabilities.set_cell(x.into(), y.into(), probability)?;
+        }
+
+        let frontier: HashSet<(u16, u16)> = potential_bombs.into_iter().collect();
+        let interior: Vec<(u16, u16)> = self
+            .grid
+            .get_iter()
+            .filter(|cell| *cell.val == Cell::Unknown && !frontier.contains(&cell.pos))
+            .map(|cell| cell.pos)
+            .collect();
+
+        // range of bombs that can end up in the counted components and the interior
+        let min_frontier: usize = tallies
+            .iter()
+            .map(|t| t.solutions.iter().position(|&n| n > 0).unwrap_or(0))
+            .sum();
+        let max_frontier: usize = tallies.iter().map(|t| t.solutions.len() - 1).sum();
+
+        let mut remaining = remaining.saturating_sub(skipped_bombs.round() as usize);
+        if !skipped.is_empty() {
+            // the estimate for the skipped components can be off, keep what is left possible
+            remaining = remaining.clamp(min_frontier, max_frontier + interior.len());
+        }
+
+        // range of bombs that can end up in the interior
+        let min_r = remaining.saturating_sub(max_frontier);
+        let max_r = remaining.min(interior.len());
+        if min_r > max_r {
+            return Err(Error::InconsistentBoard(
+                "bomb count cannot be met".to_string(),
+            ));
+        }
+
+        let weights = interior_weights(interior.len(), min_r, max_r);
+        let weight = |frontier_bombs: usize| match remaining.checked_sub(frontier_bombs) {
+            Some(r) if (min_r..=max_r).contains(&r) => weights[r - min_r],
+            _ => 0.0,
+        };
+
+        for (c, (component, tally)) in components.iter().zip(&tallies).enumerate() {
+            let others = weighted_sums(
+                tallies
+                    .iter()
+                    .enumerate()
+                    .filter(|(other, _)| *other != c)
+                    .map(|(_, tally)| tally),
+            );
+
+            // weight of each bomb count of this component, given every way to fill the rest
+            let component_weights: Vec<f64> = (0..tally.solutions.len())
+                .map(|k| {
+                    others
+                        .iter()
+                        .enumerate()
+                        .map(|(s, w)| w * weight(k + s))
+                        .sum()
+                })
+                .collect();
+
+            let total: f64 = (0..tally.solutions.len())
+                .map(|k| tally.solutions[k] as f64 * component_weights[k])
+                .sum();
+            if total == 0.0 {
+                return Err(Error::InconsistentBoard(
+                    "bomb count cannot be met".to_string(),
+                ));
+            }
+
+            for (i, &(x, y)) in component.iter().enumerate() {
+                let bomb: f64 = (0..tally.solutions.len())
+                    .map(|k| tally.bomb_count(k, i) as f64 * component_weights[k])
+                    .sum();
+                probabilities.set_cell(x.into(), y.into(), bomb / total)?;
+            }
+        }
+
+        if !interior.is_empty() {
+            let frontier_ways = weighted_sums(tallies.iter());
+            let mut total = 0.0;
+            let mut bombs = 0.0;
+            for (t, w) in frontier_ways.iter().enumerate() {
+                if let Some(r) = remaining.checked_sub(t) {
+                    total += w * weight(t);
+                    bombs += w * weight(t) * r as f64;
+                }
+            }
+
+            let probability = bombs / total / interior.len() as f64;
+            for (x, y) in interior {
+                probabilities.set_cell(x.into(), y.into(), probability)?;
+            }
+        }
+
+        for cell in self.grid.get_iter() {
+            if *cell.val == Cell::Flag {
+                let (x, y) = cell.pos;
+                probabilities.set_cell(x.into(), y.into(), 1.0)?;
+            }
+        }
+
+        Ok(probabilities)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::Topology;
+    use std::str::FromStr;
+
+    fn get_solved_str() -> &'static str {
+        "B  1  1  B  3  B
+        1  1  1  2  B  2
+        1  1  1  1  1  1
+        1  B  1  0  0  0"
+    }
+
+    fn get_pre_str() -> &'static str {
+        "?  ?  ?  ?  ?  ?
+        1  1  1  ?  ?  ?
+        1  1  1  1  1  1
+        ?  ?  ?  0  0  0"
+    }
+
+    fn assert_probability(probabilities: &Grid<f64>, x: i32, y: i32, expected: f64) {
+        let probability = probabilities.get_cell(x, y).unwrap();
+        assert!(
+            (probability - expected).abs() < 1e-9,
+            "({}, {}) = {}, expected {}",
+            x,
+            y,
+            probability,
+            expected
+        );
+    }
+
+    #[test]
+    fn bomb_probabilities_1() {
+        let board = GameBoard::from_str(get_solved_str(), get_pre_str()).unwrap();
+
+        let probabilities = board.bomb_probabilities().unwrap();
+        println!("{}\n{}", board.grid, probabilities);
+
+        // revealed
+        assert_probability(&probabilities, 0, 1, 0.0);
+        assert_probability(&probabilities, 3, 0, 0.0);
+        // frontier
+        assert_probability(&probabilities, 0, 0, 0.0);
+        assert_probability(&probabilities, 1, 0, 1.0);
+        assert_probability(&probabilities, 0, 3, 2.0 / 3.0);
+        assert_probability(&probabilities, 1, 3, 1.0 / 3.0);
+        assert_probability(&probabilities, 2, 3, 0.0);
+        assert_probability(&probabilities, 3, 3, 2.0 / 3.0);
+        assert_probability(&probabilities, 4, 2, 1.0);
+        // interior, only constrained by the bomb count
+        assert_probability(&probabilities, 4, 3, 2.0 / 3.0);
+        assert_probability(&probabilities, 5, 3, 2.0 / 3.0);
+    }
+
+    #[test]
+    fn bomb_probabilities_3() {
+        // a ring of 180 unknown cells around a row of 1s, far too many patterns to count
+        let mut solved = Grid::new(90, 3, Cell::Number(0)).with_topology(Topology::Cylinder);
+        for x in (0..90).step_by(3) {
+            solved.set_cell(x, 2, Cell::Bomb).unwrap();
+        }
+        let mut board = GameBoard::from(solved).unwrap();
+        for x in 0..90 {
+            assert!(board.reveal(x, 1).unwrap());
+        }
+
+        let probabilities = board.bomb_probabilities().unwrap();
+
+        // every 1 needs one bomb among its 6 unknown neighbours
+        assert_probability(&probabilities, 0, 0, 1.0 / 6.0);
+        assert_probability(&probabilities, 45, 2, 1.0 / 6.0);
+        assert_probability(&probabilities, 45, 1, 0.0);
+    }
+
+    #[test]
+    fn bomb_probabilities_4() {
+        let board = GameBoard::from_partial_view(
+            Grid::from_str("?  1  ?").unwrap(),
+            Grid::from_str("B  1  0").unwrap(),
+        )
+        .unwrap();
+
+        assert!(matches!(
+            board.bomb_probabilities(),
+            Err(Error::SolverLimit(_))
+        ));
+    }
+
+    #[test]
+    fn bomb_probabilities_2() {
+        let board = GameBoard::from_str(
+            "B  1
+            1  1",
+            "?  ?
+            1  1",
+        )
+        .unwrap();
+
+        let probabilities = board.bomb_probabilities().unwrap();
+
+        assert_probability(&probabilities, 0, 1, 0.5);
+        assert_probability(&probabilities, 1, 1, 0.5);
+    }
+}
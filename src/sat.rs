@@ -0,0 +1,572 @@
+use std::{collections::HashMap, fmt::Write};
+
+use crate::{
+    Cell,
+    board::{GameBoard, RevealOracle},
+    error::Error,
+    explain::{Explanation, Rule},
+    solve::{Deductions, frontier_cells},
+};
+
+/// A formula in conjunctive normal form. Variables are numbered from 1 and a literal is a variable
+/// (true) or its negation (false), as in DIMACS.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Cnf {
+    pub num_vars: usize,
+    pub clauses: Vec<Vec<i32>>,
+}
+
+impl Cnf {
+    fn new_var(&mut self) -> i32 {
+        self.num_vars += 1;
+        self.num_vars as i32
+    }
+
+    /// At most `k` of `lits` are true, using a sequential counter so the number of clauses grows
+    /// with `lits.len() * k` rather than with the number of subsets.
+    pub fn at_most(&mut self, lits: &[i32], k: usize) {
+        let n = lits.len();
+        if k >= n {
+            return;
+        }
+        if k == 0 {
+            for &lit in lits {
+                self.clauses.push(vec![-lit]);
+            }
+            return;
+        }
+
+        // counter[i][j] is true if at least j + 1 of lits[..=i] are true
+        let counter: Vec<Vec<i32>> = (0..n - 1)
+            .map(|_| (0..k).map(|_| self.new_var()).collect())
+            .collect();
+
+        self.clauses.push(vec![-lits[0], counter[0][0]]);
+        for &aux in &counter[0][1..] {
+            self.clauses.push(vec![-aux]);
+        }
+        for i in 1..n - 1 {
+            self.clauses.push(vec![-lits[i], counter[i][0]]);
+            self.clauses.push(vec![-counter[i - 1][0], counter[i][0]]);
+            for j in 1..k {
+                self.clauses
+                    .push(vec![-lits[i], -counter[i - 1][j - 1], counter[i][j]]);
+                self.clauses.push(vec![-counter[i - 1][j], counter[i][j]]);
+            }
+            self.clauses.push(vec![-lits[i], -counter[i - 1][k - 1]]);
+        }
+        self.clauses
+            .push(vec![-lits[n - 1], -counter[n - 2][k - 1]]);
+    }
+
+    /// At least `k` of `lits` are true, ie. at most `lits.len() - k` of them are false.
+    pub fn at_least(&mut self, lits: &[i32], k: usize) {
+        if k > lits.len() {
+            // unsatisfiable
+            self.clauses.push(Vec::new());
+            return;
+        }
+
+        let negated: Vec<i32> = lits.iter().map(|lit| -lit).collect();
+        self.at_most(&negated, lits.len() - k);
+    }
+
+    pub fn exactly(&mut self, lits: &[i32], k: usize) {
+        self.at_most(lits, k);
+        self.at_least(lits, k);
+    }
+
+    pub fn to_dimacs(&self) -> String {
+        let mut out = format!("p cnf {} {}\n", self.num_vars, self.clauses.len());
+        for clause in &self.clauses {
+            for lit in clause {
+                write!(out, "{} ", lit).unwrap();
+            }
+            out.push_str("0\n");
+        }
+
+        out
+    }
+
+    /// Whether the formula can be satisfied with every literal in `assumptions` true.
+    pub fn is_satisfiable(&self, assumptions: &[i32]) -> bool {
+        Dpll::new(self).solve(assumptions).is_some()
+    }
+}
+
+/// The frontier of a board as a CNF formula, where variable `i + 1` is true if `cells[i]` is a
+/// bomb. Any variables past `cells.len()` are auxiliary variables of the cardinality constraints.
+#[derive(Clone, Debug)]
+pub struct FrontierCnf {
+    pub cnf: Cnf,
+    pub cells: Vec<(u16, u16)>,
+    /// the numbers the formula was built from, each one an exactly-k constraint
+    pub numbers: Vec<(u16, u16)>,
+    pub bomb_count: bool,
+    // the variable of each cell in `cells`
+    vars: HashMap<(u16, u16), i32>,
+}
+
+impl FrontierCnf {
+    pub fn var(&self, cell: (u16, u16)) -> Option<i32> {
+        self.vars.get(&cell).copied()
+    }
+
+    /// DIMACS CNF, with comments mapping each frontier variable back to its cell.
+    pub fn to_dimacs(&self) -> String {
+        let mut out = String::new();
+        for (i, (x, y)) in self.cells.iter().enumerate() {
+            writeln!(out, "c {} = ({}, {})", i + 1, x, y).unwrap();
+        }
+        out.push_str(&self.cnf.to_dimacs());
+
+        out
+    }
+}
+
+/// A small DPLL solver with unit propagation over two watched literals and chronological
+/// backtracking. It makes no attempt at clause learning, the formulas built from a frontier are
+/// small and mostly decided by propagation.
+struct Dpll {
+    clauses: Vec<Vec<i32>>,
+    // watches[lit_index(lit)] are the clauses watching `lit`, visited when it becomes false
+    watches: Vec<Vec<usize>>,
+    values: Vec<Option<bool>>,
+    trail: Vec<i32>,
+    // trail[..propagated] have had their watches visited
+    propagated: usize,
+    // set when the formula is unsatisfiable without any assumptions
+    conflict: bool,
+}
+
+fn lit_index(lit: i32) -> usize {
+    2 * (lit.unsigned_abs() as usize - 1) + usize::from(lit < 0)
+}
+
+impl Dpll {
+    fn new(cnf: &Cnf) -> Self {
+        let mut dpll = Dpll {
+            clauses: Vec::new(),
+            watches: vec![Vec::new(); 2 * cnf.num_vars],
+            values: vec![None; cnf.num_vars + 1],
+            trail: Vec::new(),
+            propagated: 0,
+            conflict: false,
+        };
+
+        for clause in &cnf.clauses {
+            match clause[..] {
+                [] => dpll.conflict = true,
+                [lit] => {
+                    if !dpll.assign(lit) {
+                        dpll.conflict = true;
+                    }
+                }
+                _ => {
+                    dpll.watches[lit_index(clause[0])].push(dpll.clauses.len());
+                    dpll.watches[lit_index(clause[1])].push(dpll.clauses.len());
+                    dpll.clauses.push(clause.clone());
+                }
+            }
+        }
+        dpll.conflict |= !dpll.propagate();
+
+        dpll
+    }
+
+    fn value(&self, lit: i32) -> Option<bool> {
+        self.values[lit.unsigned_abs() as usize].map(|value| value == (lit > 0))
+    }
+
+    // makes `lit` true, false if it was already false
+    fn assign(&mut self, lit: i32) -> bool {
+        match self.value(lit) {
+            Some(value) => value,
+            None => {
+                self.values[lit.unsigned_abs() as usize] = Some(lit > 0);
+                self.trail.push(lit);
+                true
+            }
+        }
+    }
+
+    fn undo(&mut self, mark: usize) {
+        for lit in self.trail.drain(mark..) {
+            self.values[lit.unsigned_abs() as usize] = None;
+        }
+        self.propagated = self.propagated.min(mark);
+    }
+
+    // false on a conflict
+    fn propagate(&mut self) -> bool {
+        while self.propagated < self.trail.len() {
+            let false_lit = -self.trail[self.propagated];
+            self.propagated += 1;
+
+            let watching = std::mem::take(&mut self.watches[lit_index(false_lit)]);
+            let mut keep = Vec::with_capacity(watching.len());
+            let mut conflict = false;
+
+            for (w, &c) in watching.iter().enumerate() {
+                if conflict {
+                    keep.extend(&watching[w..]);
+                    break;
+                }
+
+                let clause = &mut self.clauses[c];
+                if clause[0] == false_lit {
+                    clause.swap(0, 1);
+                }
+                let other = clause[0];
+
+                if self.values[other.unsigned_abs() as usize] == Some(other > 0) {
+                    keep.push(c);
+                    continue;
+                }
+
+                let replacement = (2..clause.len()).find(|&k| {
+                    let lit = clause[k];
+                    self.values[lit.unsigned_abs() as usize] != Some(lit < 0)
+                });
+                if let Some(k) = replacement {
+                    clause.swap(1, k);
+                    let new_watch = clause[1];
+                    self.watches[lit_index(new_watch)].push(c);
+                    continue;
+                }
+
+                keep.push(c);
+                if !self.assign(other) {
+                    conflict = true;
+                }
+            }
+
+            self.watches[lit_index(false_lit)] = keep;
+            if conflict {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    /// A satisfying assignment (indexed by variable) with every literal in `assumptions` true.
+    fn solve(&mut self, assumptions: &[i32]) -> Option<Vec<bool>> {
+        if self.conflict {
+            return None;
+        }
+
+        let base = self.trail.len();
+        let model = self.search(assumptions);
+        self.undo(base);
+
+        model
+    }
+
+    fn search(&mut self, assumptions: &[i32]) -> Option<Vec<bool>> {
+        for &lit in assumptions {
+            if !self.assign(lit) {
+                return None;
+            }
+        }
+        if !self.propagate() {
+            return None;
+        }
+
+        // (trail length before the decision, decided literal, whether it has been flipped)
+        let mut decisions: Vec<(usize, i32, bool)> = Vec::new();
+        let mut next_var = 1;
+        loop {
+            while next_var < self.values.len() && self.values[next_var].is_some() {
+                next_var += 1;
+            }
+            if next_var == self.values.len() {
+                return Some(
+                    self.values
+                        .iter()
+                        .map(|value| value.unwrap_or(false))
+                        .collect(),
+                );
+            }
+
+            // safe first, most cells are
+            let lit = -(next_var as i32);
+            decisions.push((self.trail.len(), lit, false));
+            self.assign(lit);
+
+            while !self.propagate() {
+                loop {
+                    let (mark, lit, flipped) = decisions.pop()?;
+                    self.undo(mark);
+                    if !flipped {
+                        decisions.push((mark, -lit, true));
+                        self.assign(-lit);
+                        break;
+                    }
+                }
+                // backtracking unassigns variables anywhere before `next_var`
+                next_var = 1;
+            }
+        }
+    }
+}
+
+impl<O: RevealOracle> GameBoard<O> {
+    /// The frontier as CNF, one exactly-k constraint per number with unknown neighbours. With
+    /// `bomb_count` the remaining bomb count is added too, which bounds the frontier to between
+    /// `remaining - interior` and `remaining` bombs.
+    pub fn frontier_cnf(&self, bomb_count: bool) -> Result<FrontierCnf, Error> {
+        let numbers = self.frontier_numbers()?;
+        let cells = frontier_cells(&numbers);
+        let vars: HashMap<(u16, u16), i32> = cells
+            .iter()
+            .enumerate()
+            .map(|(i, &pos)| (pos, i as i32 + 1))
+            .collect();
+
+        let mut cnf = Cnf {
+            num_vars: cells.len(),
+            clauses: Vec::new(),
+        };
+        for number in &numbers {
+            let mut lits: Vec<i32> = number.unknown.iter().map(|pos| vars[pos]).collect();
+            lits.sort_unstable();
+            cnf.exactly(&lits, number.remaining.into());
+        }
+
+        let remaining = self.remaining_bombs();
+        if bomb_count && let Some(remaining) = remaining {
+            let unknown = self
+                .grid
+                .get_iter()
+                .filter(|cell| *cell.val == Cell::Unknown)
+                .count();
+            let interior = unknown - cells.len();
+            let lits: Vec<i32> = (1..=cells.len() as i32).collect();
+
+            cnf.at_most(&lits, remaining as usize);
+            cnf.at_least(&lits, (remaining as usize).saturating_sub(interior));
+        }
+
+        Ok(FrontierCnf {
+            cnf,
+            cells,
+            numbers: numbers.iter().map(|number| number.pos).collect(),
+            bomb_count: bomb_count && remaining.is_some(),
+            vars,
+        })
+    }
+
+    /// Every frontier cell that is forced, found by asking the DPLL solver whether the frontier
+    /// (and the remaining bomb count) can still be satisfied with the cell as a bomb and as safe.
+    /// The interior is checked the same way, by asking whether the frontier can leave any bombs
+    /// (or any safe cells) for it. Finds the same deductions as `deductions` without enumerating
+    /// patterns.
+    pub fn sat_deductions(&self) -> Result<Deductions, Error> {
+        let mut deductions = Deductions::default();
+
+        let frontier = self.frontier_cnf(true)?;
+        let mut dpll = Dpll::new(&frontier.cnf);
+        let Some(model) = dpll.solve(&[]) else {
+            return Err(Error::InconsistentBoard(
+                "no bomb pattern fits the frontier".to_string(),
+            ));
+        };
+
+        // seen[i][b] is whether cell i has been safe (b = 0) or a bomb (b = 1) in any model found so
+        // far, as every model answers a query for each of its cells
+        let mut seen = vec![[false; 2]; frontier.cells.len()];
+        fn record(seen: &mut [[bool; 2]], model: &[bool]) {
+            for (i, seen) in seen.iter_mut().enumerate() {
+                seen[usize::from(model[i + 1])] = true;
+            }
+        }
+        record(&mut seen, &model);
+
+        let rule = Rule::Sat {
+            numbers: frontier.numbers.clone(),
+            bomb_count: frontier.bomb_count,
+        };
+        for (i, &cell) in frontier.cells.iter().enumerate() {
+            for bomb in [false, true] {
+                if seen[i][usize::from(bomb)] {
+                    continue;
+                }
+
+                let var = i as i32 + 1;
+                match dpll.solve(&[if bomb { var } else { -var }]) {
+                    Some(model) => record(&mut seen, &model),
+                    None => {
+                        // the cell can never be `bomb`, so it is always the opposite
+                        if bomb {
+                            deductions.safe.push(cell);
+                        } else {
+                            deductions.bombs.push(cell);
+                        }
+                        deductions.explanations.push(Explanation {
+                            cell,
+                            bomb: !bomb,
+                            rule: rule.clone(),
+                        });
+                    }
+                }
+            }
+        }
+
+        let interior: Vec<(u16, u16)> = self
+            .grid
+            .get_iter()
+            .filter(|cell| *cell.val == Cell::Unknown && frontier.var(cell.pos).is_none())
+            .map(|cell| cell.pos)
+            .collect();
+        if let Some(remaining) = self.remaining_bombs()
+            && !interior.is_empty()
+        {
+            let remaining = remaining as usize;
+            let lits: Vec<i32> = (1..=frontier.cells.len() as i32).collect();
+
+            // the interior is all safe if the frontier cannot hold fewer than `remaining` bombs, and
+            // all bombs if it cannot hold more than `remaining - interior`
+            let mut fewer = frontier.cnf.clone();
+            fewer.at_most(&lits, remaining.saturating_sub(1));
+            let mut more = frontier.cnf.clone();
+            more.at_least(&lits, remaining.saturating_sub(interior.len()) + 1);
+
+            let bomb = if remaining == 0 || !fewer.is_satisfiable(&[]) {
+                Some(false)
+            } else if remaining >= interior.len() && !more.is_satisfiable(&[]) {
+                Some(true)
+            } else {
+                None
+            };
+
+            if let Some(bomb) = bomb {
+                let rule = Rule::BombCount {
+                    remaining: remaining as u32,
+                    interior: interior.len(),
+                };
+                for &cell in &interior {
+                    if bomb {
+                        deductions.bombs.push(cell);
+                    } else {
+                        deductions.safe.push(cell);
+                    }
+                    deductions.explanations.push(Explanation {
+                        cell,
+                        bomb,
+                        rule: rule.clone(),
+                    });
+                }
+            }
+        }
+
+        deductions.normalise();
+
+        Ok(deductions)
+    }
+
+    pub fn sat_solve_step(&mut self) -> Result<bool, Error> {
+        let pre_board = self.grid.clone();
+
+        let deductions = self.sat_deductions()?;
+        self.apply_deductions(&deductions)?;
+
+        Ok(pre_board != self.grid)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+    use crate::{fixtures::get_count_safe_pre_str, grid::Grid, solve::deduce};
+
+    #[test]
+    fn cardinality_1() {
+        // every assignment of 5 variables satisfies exactly(k) iff k of them are true
+        for k in 0..=6 {
+            let mut cnf = Cnf {
+                num_vars: 5,
+                clauses: Vec::new(),
+            };
+            let lits: Vec<i32> = (1..=5).collect();
+            cnf.exactly(&lits, k);
+
+            for pattern in 0u32..32 {
+                let assumptions: Vec<i32> = lits
+                    .iter()
+                    .map(|&lit| {
+                        if pattern >> (lit - 1) & 1 == 1 {
+                            lit
+                        } else {
+                            -lit
+                        }
+                    })
+                    .collect();
+                assert_eq!(
+                    cnf.is_satisfiable(&assumptions),
+                    pattern.count_ones() as usize == k,
+                    "k = {}, pattern = {:05b}",
+                    k,
+                    pattern
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn dpll_1() {
+        // (a or b) and (not a or b) and (a or not b) and (not a or not b)
+        let cnf = Cnf {
+            num_vars: 2,
+            clauses: vec![vec![1, 2], vec![-1, 2], vec![1, -2], vec![-1, -2]],
+        };
+        assert!(!cnf.is_satisfiable(&[]));
+
+        let cnf = Cnf {
+            num_vars: 3,
+            clauses: vec![vec![1, 2], vec![-1, 3], vec![-2, 3], vec![-3, -1]],
+        };
+        assert!(cnf.is_satisfiable(&[]));
+        assert!(!cnf.is_satisfiable(&[-3]));
+        assert!(!cnf.is_satisfiable(&[1]));
+    }
+
+    #[test]
+    fn frontier_cnf_1() {
+        let grid = Grid::from_str(get_count_safe_pre_str()).unwrap();
+        let board = GameBoard::from_view(grid, 1, crate::board::NoOracle).unwrap();
+
+        let frontier = board.frontier_cnf(false).unwrap();
+        assert_eq!(frontier.cells, vec![(1, 0), (1, 1), (1, 2), (1, 3), (1, 4)]);
+        assert_eq!(frontier.var((1, 2)), Some(3));
+        assert!(!frontier.bomb_count);
+
+        let dimacs = frontier.to_dimacs();
+        assert!(dimacs.starts_with("c 1 = (1, 0)\n"));
+        assert!(dimacs.contains(&format!(
+            "p cnf {} {}\n",
+            frontier.cnf.num_vars,
+            frontier.cnf.clauses.len()
+        )));
+        assert!(dimacs.ends_with(" 0\n"));
+    }
+
+    #[test]
+    fn sat_deductions_1() {
+        // the same deductions as permuting, including the interior from the bomb count
+        let grid = Grid::from_str(get_count_safe_pre_str()).unwrap();
+
+        for num_bombs in [1, 6] {
+            let board =
+                GameBoard::from_view(grid.clone(), num_bombs, crate::board::NoOracle).unwrap();
+            let sat = board.sat_deductions().unwrap();
+            let permute = deduce(&grid, num_bombs).unwrap();
+
+            assert_eq!(sat.safe, permute.safe);
+            assert_eq!(sat.bombs, permute.bombs);
+            assert_eq!(sat.explanations.len(), sat.safe.len() + sat.bombs.len());
+        }
+    }
+}
@@ -1,33 +1,544 @@
-use crate::{Cell, board::GameBoard};
+use std::collections::{HashMap, HashSet};
 
-fn calc_result_patterns(
-    potential_bombs: &Vec<(u8, u8)>,
-    valid_patterns: &Vec<u128>,
-    end_pattern: u128,
-) -> (u128, u128) {
-    let mut flag_pattern = end_pattern - 1;
-    let mut safe_pattern = 0u128;
+use crate::{
+    Cell,
+    board::{GameBoard, NoOracle, RevealOracle},
+    error::Error,
+    explain::{Explanation, Rule},
+    grid::Grid,
+};
 
-    for pattern in valid_patterns {
-        flag_pattern &= pattern;
-        safe_pattern |= pattern;
+// upper bound on the number of partial assignments explored for a single component before it is
+// considered too complex
+const MAX_SEARCH_NODES: u64 = 10_000_000;
+
+/// Cells that are guaranteed to be bombs or guaranteed to be safe.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Deductions {
+    pub safe: Vec<(u16, u16)>,
+    pub bombs: Vec<(u16, u16)>,
+    /// the number of valid bomb patterns of each frontier component that was fully searched
+    pub valid_patterns: Vec<u64>,
+    /// why each cell in `safe` and `bombs` was deduced, in the same (y, x) order
+    pub explanations: Vec<Explanation>,
+}
+
+impl Deductions {
+    pub fn is_empty(&self) -> bool {
+        self.safe.is_empty() && self.bombs.is_empty()
+    }
+
+    // sorts every cell by (y, x) and removes duplicates, keeping the first explanation found for
+    // each cell
+    pub(crate) fn normalise(&mut self) {
+        for cells in [&mut self.safe, &mut self.bombs] {
+            cells.sort_by_key(|&(x, y)| (y, x));
+            cells.dedup();
+        }
+        self.explanations
+            .sort_by_key(|explanation| (explanation.cell.1, explanation.cell.0));
+        self.explanations
+            .dedup_by_key(|explanation| explanation.cell);
+    }
+}
+
+// bombs still unaccounted for around a number cell and its unknown neighbours
+type NumberUnknowns = (u8, HashSet<(u16, u16)>);
+
+/// A number cell with unknown neighbours.
+pub(crate) struct FrontierNumber {
+    pub pos: (u16, u16),
+    // bombs still unaccounted for (from `flag_adj_grid`)
+    pub remaining: u8,
+    pub unknown: HashSet<(u16, u16)>,
+}
+
+// every unknown neighbour of `numbers`, in (y, x) order
+pub(crate) fn frontier_cells(numbers: &[FrontierNumber]) -> Vec<(u16, u16)> {
+    let mut cells: Vec<(u16, u16)> = numbers
+        .iter()
+        .flat_map(|number| number.unknown.iter().copied())
+        .collect();
+    cells.sort_by_key(|&(x, y)| (y, x));
+    cells.dedup();
+
+    cells
+}
+
+/// One row of the frontier as a linear system, `sum(coefficients[j] * cell j) == total` where each
+/// cell is 1 for a bomb and 0 if safe.
+#[derive(Clone)]
+struct Equation {
+    coefficients: Vec<i64>,
+    total: i64,
+    // sources[n] is whether number n of the system (or the bomb count, as the last entry) was
+    // combined into this row
+    sources: Vec<bool>,
+}
+
+fn gcd(a: i64, b: i64) -> i64 {
+    if b == 0 { a.abs() } else { gcd(b, a % b) }
+}
+
+impl Equation {
+    // divides through by the gcd so coefficients stay small during elimination
+    fn reduce(&mut self) {
+        let divisor = self
+            .coefficients
+            .iter()
+            .fold(self.total, |acc, &coefficient| gcd(acc, coefficient));
+        if divisor > 1 {
+            for coefficient in self.coefficients.iter_mut() {
+                *coefficient /= divisor;
+            }
+            self.total /= divisor;
+        }
+    }
+
+    /// Removes `column` from this row using `pivot`, or `None` if the coefficients overflowed.
+    fn eliminate(&self, pivot: &Equation, column: usize) -> Option<Equation> {
+        let factor = self.coefficients[column];
+        let scale = pivot.coefficients[column];
+
+        let mut coefficients = Vec::with_capacity(self.coefficients.len());
+        for (&coefficient, &pivot_coefficient) in self.coefficients.iter().zip(&pivot.coefficients)
+        {
+            coefficients.push(
+                coefficient
+                    .checked_mul(scale)?
+                    .checked_sub(factor.checked_mul(pivot_coefficient)?)?,
+            );
+        }
+        let total = self
+            .total
+            .checked_mul(scale)?
+            .checked_sub(factor.checked_mul(pivot.total)?)?;
+
+        let mut equation = Equation {
+            coefficients,
+            total,
+            sources: self
+                .sources
+                .iter()
+                .zip(&pivot.sources)
+                .map(|(&a, &b)| a || b)
+                .collect(),
+        };
+        equation.reduce();
+
+        Some(equation)
+    }
+
+    // replaces cell `column` with its known value, merging in the sources it was deduced from
+    fn substitute(&mut self, column: usize, bomb: bool, sources: &[bool]) {
+        let coefficient = std::mem::take(&mut self.coefficients[column]);
+        if coefficient == 0 {
+            return;
+        }
+
+        if bomb {
+            self.total -= coefficient;
+        }
+        for (source, &other) in self.sources.iter_mut().zip(sources) {
+            *source |= other;
+        }
+    }
+
+    /// Every cell in the row and whether it is a bomb, if only one 0/1 assignment of the row's cells
+    /// can reach `total`. That is when `total` is the largest (every positive coefficient a bomb) or
+    /// smallest (every negative coefficient a bomb) sum the row can make.
+    fn forced(&self) -> Option<Vec<(usize, bool)>> {
+        let max: i64 = self.coefficients.iter().filter(|&&c| c > 0).sum();
+        let min: i64 = self.coefficients.iter().filter(|&&c| c < 0).sum();
+        if max == 0 && min == 0 {
+            return None;
+        }
+
+        let positive_bombs = if self.total == max {
+            true
+        } else if self.total == min {
+            false
+        } else {
+            return None;
+        };
+
+        Some(
+            self.coefficients
+                .iter()
+                .enumerate()
+                .filter(|(_, coefficient)| **coefficient != 0)
+                .map(|(j, &coefficient)| (j, (coefficient > 0) == positive_bombs))
+                .collect(),
+        )
+    }
+}
+
+/// Set of cells of a frontier component, one bit per cell index.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Bitboard {
+    words: Vec<u64>,
+}
+
+impl Bitboard {
+    fn new(num_cells: usize) -> Self {
+        Bitboard {
+            words: vec![0; num_cells.div_ceil(64)],
+        }
+    }
+
+    fn set(&mut self, i: usize) {
+        self.words[i / 64] |= 1 << (i % 64);
+    }
+
+    fn clear(&mut self, i: usize) {
+        self.words[i / 64] &= !(1 << (i % 64));
+    }
+
+    fn ones(&self) -> impl Iterator<Item = usize> + '_ {
+        self.words.iter().enumerate().flat_map(|(w, &word)| {
+            let mut word = word;
+            std::iter::from_fn(move || {
+                if word == 0 {
+                    return None;
+                }
+                let bit = word.trailing_zeros() as usize;
+                word &= word - 1;
+                Some(w * 64 + bit)
+            })
+        })
     }
+}
 
-    // logical OR all valid patterns, if any digit is 0, it is guaranteed to be a safe
-    // logical AND all valid patterns, if any digit is 1, it is guaranteed to be a bomb
+/// A number cell restricted to the cells of a single frontier component.
+struct Constraint {
+    // the unknown neighbours of the number cell as a bitmask over the component, only keeping the
+    // (word index, word) pairs of the mask that have a bit set
+    mask: Vec<(usize, u64)>,
+    // the number of bits set in the mask
+    cells: u32,
+    // bombs still unaccounted for around the number cell (from `flag_adj_grid`)
+    remaining: u8,
+}
+
+impl Constraint {
+    // `cells` are the indices of the unknown neighbours in increasing order
+    fn new(cells: &[usize], remaining: u8) -> Self {
+        let mut mask: Vec<(usize, u64)> = Vec::new();
+        for &i in cells {
+            match mask.last_mut() {
+                Some((w, word)) if *w == i / 64 => *word |= 1 << (i % 64),
+                _ => mask.push((i / 64, 1 << (i % 64))),
+            }
+        }
 
-    println!(
-        "flag_pattern: {:0width$b}\nsafe_pattern: {:0width$b}",
-        flag_pattern,
-        safe_pattern,
-        width = potential_bombs.len()
-    );
+        Constraint {
+            mask,
+            cells: cells.len() as u32,
+            remaining,
+        }
+    }
+
+    // whether the number can still be satisfied, given the bombs in `pattern` and the number of
+    // its cells that are still undecided
+    fn satisfiable(&self, pattern: &Bitboard, undecided: u32) -> bool {
+        let bombs: u32 = self
+            .mask
+            .iter()
+            .map(|&(w, word)| (pattern.words[w] & word).count_ones())
+            .sum();
+        let remaining = u32::from(self.remaining);
+
+        bombs <= remaining && remaining - bombs <= undecided
+    }
+}
 
-    (flag_pattern, safe_pattern)
+/// The number cells around a frontier component compiled into bitmasks over its cells, so a
+/// pattern is checked against a number with popcounts instead of by walking the grid.
+struct CompiledComponent {
+    num_cells: usize,
+    constraints: Vec<Constraint>,
+    // the constraints touching each cell, with the number of cells of the constraint left
+    // undecided once that cell is assigned (cells are assigned in index order)
+    touching: Vec<Vec<(usize, u32)>>,
 }
 
-impl GameBoard {
-    pub fn simple_solve_step(&mut self) -> Result<bool, String> {
+impl CompiledComponent {
+    // every cell touches a number, so the component can never hold more bombs than its numbers
+    // still need between them
+    fn max_bombs(&self) -> usize {
+        let needed: usize = self
+            .constraints
+            .iter()
+            .map(|constraint| usize::from(constraint.remaining))
+            .sum();
+        needed.min(self.num_cells)
+    }
+
+    // `numbers` holds the cell indices (in increasing order) and remaining bombs of every number
+    fn new(num_cells: usize, numbers: Vec<(Vec<usize>, u8)>) -> Self {
+        let mut touching = vec![Vec::new(); num_cells];
+        for (i, (cells, _)) in numbers.iter().enumerate() {
+            let mut undecided = cells.len() as u32;
+            for &cell in cells {
+                undecided -= 1;
+                touching[cell].push((i, undecided));
+            }
+        }
+
+        CompiledComponent {
+            num_cells,
+            constraints: numbers
+                .iter()
+                .map(|(cells, remaining)| Constraint::new(cells, *remaining))
+                .collect(),
+            touching,
+        }
+    }
+}
+
+/// Summary of every valid bomb pattern of a component, broken down by the number of bombs in the
+/// pattern so it can be combined with the global bomb count.
+pub struct ComponentTally {
+    // solutions[k] is the number of valid patterns with exactly k bombs, up to the most bombs the
+    // component can hold
+    pub solutions: Vec<u64>,
+    // bomb_counts[k][i] is the number of those patterns in which cell i is a bomb. a row is only
+    // allocated once a pattern with k bombs is found, as most bomb counts never occur
+    bomb_counts: Vec<Vec<u64>>,
+}
+
+impl ComponentTally {
+    pub fn total_solutions(&self) -> u64 {
+        self.solutions.iter().sum()
+    }
+
+    /// The number of valid patterns with exactly `k` bombs in which cell `i` is a bomb.
+    pub fn bomb_count(&self, k: usize, i: usize) -> u64 {
+        self.bomb_counts
+            .get(k)
+            .and_then(|row| row.get(i))
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Whether cell `i` is a bomb (`Some(true)`) or safe (`Some(false)`) in every valid pattern
+    /// whose bomb count is allowed by `feasible`.
+    fn certainty(&self, i: usize, feasible: impl Fn(usize) -> bool) -> Option<bool> {
+        let mut always_bomb = true;
+        let mut always_safe = true;
+        let mut any = false;
+
+        for (k, &solutions) in self.solutions.iter().enumerate() {
+            if solutions == 0 || !feasible(k) {
+                continue;
+            }
+
+            any = true;
+            always_bomb &= self.bomb_count(k, i) == solutions;
+            always_safe &= self.bomb_count(k, i) == 0;
+        }
+
+        match (any, always_bomb, always_safe) {
+            (false, _, _) => None,
+            (true, true, _) => Some(true),
+            (true, _, true) => Some(false),
+            _ => None,
+        }
+    }
+}
+
+/// Bomb counts that remain possible for every component and for the interior (unknown cells not
+/// touching any number) once the total number of remaining bombs is taken into account.
+struct GlobalFeasibility {
+    // components[c][k] is whether component c can hold exactly k bombs
+    components: Vec<Vec<bool>>,
+    // interior[j] is whether the interior can hold exactly j bombs
+    interior: Vec<bool>,
+}
+
+// sums[s] is whether some combination of valid patterns across `tallies` holds exactly s bombs
+fn achievable_sums<'a>(tallies: impl Iterator<Item = &'a ComponentTally>) -> Vec<bool> {
+    let mut sums = vec![true];
+
+    for tally in tallies {
+        let mut next = vec![false; sums.len() + tally.solutions.len() - 1];
+        for (s, _) in sums.iter().enumerate().filter(|(_, possible)| **possible) {
+            for (k, _) in tally.solutions.iter().enumerate().filter(|(_, n)| **n > 0) {
+                next[s + k] = true;
+            }
+        }
+        sums = next;
+    }
+
+    sums
+}
+
+impl GlobalFeasibility {
+    fn new(tallies: &[&ComponentTally], remaining: usize, interior: usize) -> Option<Self> {
+        let fits = |frontier_bombs: usize| {
+            frontier_bombs <= remaining && remaining - frontier_bombs <= interior
+        };
+
+        let mut components = Vec::new();
+        for (c, tally) in tallies.iter().enumerate() {
+            let others = achievable_sums(
+                tallies
+                    .iter()
+                    .enumerate()
+                    .filter(|(other, _)| *other != c)
+                    .map(|(_, tally)| *tally),
+            );
+
+            components.push(
+                (0..tally.solutions.len())
+                    .map(|k| {
+                        tally.solutions[k] > 0
+                            && others
+                                .iter()
+                                .enumerate()
+                                .any(|(s, possible)| *possible && fits(k + s))
+                    })
+                    .collect(),
+            );
+        }
+
+        let mut interior_feasible = vec![false; interior + 1];
+        for (t, _) in achievable_sums(tallies.iter().copied())
+            .iter()
+            .enumerate()
+            .filter(|(t, possible)| **possible && fits(*t))
+        {
+            interior_feasible[remaining - t] = true;
+        }
+
+        // no combination of patterns agrees with the bomb count, the board is inconsistent
+        if !interior_feasible.contains(&true) {
+            return None;
+        }
+
+        Some(GlobalFeasibility {
+            components,
+            interior: interior_feasible,
+        })
+    }
+}
+
+/// Backtracking search over a single frontier component, assigning one cell at a time and
+/// pruning as soon as a number cell can no longer be satisfied.
+struct ComponentSearch<'a> {
+    component: &'a CompiledComponent,
+    // bombs in the current partial assignment
+    pattern: Bitboard,
+    bombs: usize,
+    // patterns with more bombs than this are never valid
+    max_bombs: usize,
+    nodes: u64,
+    tally: ComponentTally,
+}
+
+impl<'a> ComponentSearch<'a> {
+    fn new(component: &'a CompiledComponent) -> Self {
+        let max_bombs = component.max_bombs();
+
+        ComponentSearch {
+            component,
+            pattern: Bitboard::new(component.num_cells),
+            bombs: 0,
+            max_bombs,
+            nodes: 0,
+            tally: ComponentTally {
+                solutions: vec![0; max_bombs + 1],
+                bomb_counts: vec![Vec::new(); max_bombs + 1],
+            },
+        }
+    }
+
+    fn run(mut self) -> Result<ComponentTally, Error> {
+        // a constraint that is already unsatisfiable has no valid patterns at all
+        if self
+            .component
+            .constraints
+            .iter()
+            .any(|c| !c.satisfiable(&self.pattern, c.cells))
+        {
+            return Ok(self.tally);
+        }
+
+        self.search()?;
+        Ok(self.tally)
+    }
+
+    // every constraint has been closed off with exactly 0 bombs needed
+    fn record(&mut self) {
+        let num_cells = self.component.num_cells;
+
+        self.tally.solutions[self.bombs] += 1;
+        let counts = &mut self.tally.bomb_counts[self.bombs];
+        if counts.is_empty() {
+            counts.resize(num_cells, 0);
+        }
+        for i in self.pattern.ones() {
+            counts[i] += 1;
+        }
+    }
+
+    // depth first over the cells in index order, trying bomb before safe. the path is kept in
+    // `tried` instead of recursing, as a long frontier can be far deeper than the stack
+    fn search(&mut self) -> Result<(), Error> {
+        let num_cells = self.component.num_cells;
+        // how many of the values (bomb, then safe) have been tried for each cell on the path
+        let mut tried = vec![0u8; num_cells];
+        let mut cell = 0;
+
+        loop {
+            if cell == num_cells {
+                self.record();
+            } else if tried[cell] < 2 {
+                self.nodes += 1;
+                if self.nodes > MAX_SEARCH_NODES {
+                    return Err(Error::SolverLimit(format!(
+                        "component of {} cells is too complex",
+                        num_cells
+                    )));
+                }
+
+                let is_bomb = tried[cell] == 0;
+                tried[cell] += 1;
+                if is_bomb {
+                    self.pattern.set(cell);
+                } else {
+                    self.pattern.clear(cell);
+                }
+
+                // only the numbers around this cell can have changed
+                if self.bombs + usize::from(is_bomb) <= self.max_bombs
+                    && self.component.touching[cell].iter().all(|&(c, undecided)| {
+                        self.component.constraints[c].satisfiable(&self.pattern, undecided)
+                    })
+                {
+                    self.bombs += usize::from(is_bomb);
+                    cell += 1;
+                }
+                continue;
+            } else {
+                // both values tried, so the cell was last left safe
+                tried[cell] = 0;
+            }
+
+            // step back to the previous cell, undoing its bomb if it is still set
+            if cell == 0 {
+                return Ok(());
+            }
+            cell -= 1;
+            if tried[cell] == 1 {
+                self.bombs -= 1;
+            }
+        }
+    }
+}
+
+impl<O: RevealOracle> GameBoard<O> {
+    pub fn simple_solve_step(&mut self) -> Result<bool, Error> {
         let pre_board = self.grid.clone();
 
         self.place_all_flags()?;
@@ -36,121 +547,610 @@ impl GameBoard {
         Ok(pre_board != self.grid)
     }
 
-    fn is_valid_bomb_pattern(
-        &self,
-        potential_bombs: &Vec<(u8, u8)>,
-        pattern: u128,
-    ) -> Result<bool, String> {
-        let mut board = self.clone();
+    /// Repeatedly applies the deterministic solve steps until the board is solved or no more
+    /// progress can be made, returning whether it was solved.
+    pub fn solve(&mut self) -> Result<bool, Error> {
+        loop {
+            if self.is_solved()? {
+                return Ok(true);
+            }
+
+            let progress = self.simple_solve_step()?
+                || self.pair_solve_step()?
+                || self.linear_solve_step()?
+                || self.permute_solve_step()?;
+            if !progress {
+                return Ok(false);
+            }
+        }
+    }
 
-        let mut current_pattern = pattern;
-        let mut i = 0;
+    /// Every number with unknown neighbours, with the bombs it still needs and those neighbours.
+    pub(crate) fn frontier_numbers(&self) -> Result<Vec<FrontierNumber>, Error> {
+        let mut numbers = Vec::new();
+        for cell in self.grid.get_iter() {
+            let (x, y) = cell.pos;
+            let (Cell::Number(_), Some(Some(remaining))) =
+                (cell.val, self.flag_adj_grid.get_cell(x.into(), y.into()))
+            else {
+                continue;
+            };
+
+            let unknown: HashSet<(u16, u16)> = self
+                .grid
+                .adj_cells(x, y, Some(HashSet::from([Cell::Unknown])))?
+                .into_iter()
+                .collect();
+            if !unknown.is_empty() {
+                numbers.push(FrontierNumber {
+                    pos: (x, y),
+                    remaining,
+                    unknown,
+                });
+            }
+        }
 
-        while current_pattern > 0 {
-            let is_bomb = (current_pattern & 0b1) == 1;
+        Ok(numbers)
+    }
 
-            if is_bomb {
-                let (x, y) = potential_bombs[i];
-                if board.place_flag(x, y).is_err() {
-                    return Ok(false);
+    /// Compares every pair of numbers whose unknown neighbours overlap. If `more` needs exactly as
+    /// many more bombs than `less` as it has unknown neighbours that `less` does not touch, those
+    /// neighbours are all bombs and the unknown neighbours of `less` that `more` does not touch are
+    /// all safe. This covers the subset rule (eg. 1-1 against a wall, where the difference is 0) and
+    /// the 1-2 pattern.
+    pub fn pair_deductions(&self) -> Result<Deductions, Error> {
+        let mut deductions = Deductions::default();
+
+        let numbers: HashMap<(u16, u16), NumberUnknowns> = self
+            .frontier_numbers()?
+            .into_iter()
+            .map(|number| (number.pos, (number.remaining, number.unknown)))
+            .collect();
+
+        for (&less, (less_remaining, less_unknown)) in &numbers {
+            // numbers sharing an unknown neighbour with `less` are neighbours of that cell, which
+            // also holds across the joined edges of a wrapping grid
+            let mut candidates: Vec<(u16, u16)> = less_unknown
+                .iter()
+                .flat_map(|&(x, y)| self.grid.neighbours(x, y))
+                .filter(|&more| more != less && numbers.contains_key(&more))
+                .collect();
+            candidates.sort_unstable();
+            candidates.dedup();
+
+            for more in candidates {
+                let (more_remaining, more_unknown) = &numbers[&more];
+
+                if more_remaining < less_remaining {
+                    continue;
+                }
+
+                let difference = more_remaining - less_remaining;
+                let more_only: Vec<(u16, u16)> =
+                    more_unknown.difference(less_unknown).copied().collect();
+                if more_only.len() != difference as usize {
+                    continue;
+                }
+
+                let rule = Rule::Pair {
+                    less,
+                    more,
+                    difference,
+                };
+                for &cell in &more_only {
+                    deductions.bombs.push(cell);
+                    deductions.explanations.push(Explanation {
+                        cell,
+                        bomb: true,
+                        rule: rule.clone(),
+                    });
+                }
+                for &cell in less_unknown.difference(more_unknown) {
+                    deductions.safe.push(cell);
+                    deductions.explanations.push(Explanation {
+                        cell,
+                        bomb: false,
+                        rule: rule.clone(),
+                    });
+                }
+            }
+        }
+
+        deductions.normalise();
+
+        Ok(deductions)
+    }
+
+    pub fn pair_solve_step(&mut self) -> Result<bool, Error> {
+        let pre_board = self.grid.clone();
+
+        let deductions = self.pair_deductions()?;
+        self.apply_deductions(&deductions)?;
+
+        Ok(pre_board != self.grid)
+    }
+
+    /// Writes the frontier as a linear system, one row per number and one column per unknown cell
+    /// next to a number, row reduces it and uses the fact that every cell is 0 or 1 to find reduced
+    /// rows with only one solution. When every unknown cell is on the frontier the remaining bomb
+    /// count is added as another row. Unlike `deductions` this runs in polynomial time however wide
+    /// the frontier is, at the cost of missing deductions that need a case split.
+    pub fn linear_deductions(&self) -> Result<Deductions, Error> {
+        let mut deductions = Deductions::default();
+
+        let numbers = self.frontier_numbers()?;
+        let columns = frontier_cells(&numbers);
+
+        let index: HashMap<(u16, u16), usize> = columns
+            .iter()
+            .enumerate()
+            .map(|(j, &pos)| (pos, j))
+            .collect();
+
+        let mut rows: Vec<Equation> = numbers
+            .iter()
+            .enumerate()
+            .map(|(n, number)| {
+                let mut coefficients = vec![0; columns.len()];
+                for pos in &number.unknown {
+                    coefficients[index[pos]] = 1;
+                }
+                let mut sources = vec![false; numbers.len() + 1];
+                sources[n] = true;
+
+                Equation {
+                    coefficients,
+                    total: number.remaining.into(),
+                    sources,
+                }
+            })
+            .collect();
+
+        let unknown_count = self
+            .grid
+            .get_iter()
+            .filter(|cell| *cell.val == Cell::Unknown)
+            .count();
+        if unknown_count == columns.len()
+            && let Some(remaining) = self.remaining_bombs()
+        {
+            let mut sources = vec![false; numbers.len() + 1];
+            sources[numbers.len()] = true;
+            rows.push(Equation {
+                coefficients: vec![1; columns.len()],
+                total: remaining.into(),
+                sources,
+            });
+        }
+
+        // reduced row echelon form, rows that overflow are dropped which only loses information
+        let mut pivot_row = 0;
+        for column in 0..columns.len() {
+            let Some(found) = (pivot_row..rows.len()).find(|&r| rows[r].coefficients[column] != 0)
+            else {
+                continue;
+            };
+            rows.swap(pivot_row, found);
+
+            let pivot = rows[pivot_row].clone();
+            for (r, row) in rows.iter_mut().enumerate() {
+                if r == pivot_row || row.coefficients[column] == 0 {
+                    continue;
                 }
+                *row = row.eliminate(&pivot, column).unwrap_or(Equation {
+                    coefficients: vec![0; columns.len()],
+                    total: 0,
+                    sources: vec![false; numbers.len() + 1],
+                });
             }
 
-            current_pattern >>= 1;
-            i += 1;
+            pivot_row += 1;
         }
 
-        // TODO: instead of iterating over all cells in the grid, i only need to check all number cells
-        // around a potential bomb
-        for cell in board.grid.get_iter() {
-            let (x, y) = cell.pos;
-            if let Cell::Number(_) = cell.val {
-                let flag_adj_val = board.flag_adj_grid.get_cell(x.into(), y.into());
-                if flag_adj_val != Some(Some(0)) {
-                    return Ok(false);
+        // cells forced by one row are substituted into the others, which may force more cells.
+        // known[j] is whether cell j is a bomb and the sources of the row that forced it
+        let mut known: Vec<Option<(bool, Vec<bool>)>> = vec![None; columns.len()];
+        let mut progress = true;
+        while progress {
+            progress = false;
+
+            for row in rows.iter_mut() {
+                for (j, cell) in known.iter().enumerate() {
+                    if let Some((bomb, sources)) = cell {
+                        row.substitute(j, *bomb, sources);
+                    }
                 }
+
+                let Some(forced) = row.forced() else {
+                    continue;
+                };
+
+                let rule = Rule::Linear {
+                    numbers: numbers
+                        .iter()
+                        .zip(&row.sources)
+                        .filter(|(_, used)| **used)
+                        .map(|(number, _)| number.pos)
+                        .collect(),
+                    cells: forced.len(),
+                    bomb_count: row.sources[numbers.len()],
+                };
+                for (j, bomb) in forced {
+                    known[j] = Some((bomb, row.sources.clone()));
+                    if bomb {
+                        deductions.bombs.push(columns[j]);
+                    } else {
+                        deductions.safe.push(columns[j]);
+                    }
+                    deductions.explanations.push(Explanation {
+                        cell: columns[j],
+                        bomb,
+                        rule: rule.clone(),
+                    });
+                }
+                progress = true;
             }
         }
 
-        Ok(true)
+        deductions.normalise();
+
+        Ok(deductions)
+    }
+
+    pub fn linear_solve_step(&mut self) -> Result<bool, Error> {
+        let pre_board = self.grid.clone();
+
+        let deductions = self.linear_deductions()?;
+        self.apply_deductions(&deductions)?;
+
+        Ok(pre_board != self.grid)
     }
 
-    fn potential_bombs(&self) -> Result<Vec<(u8, u8)>, String> {
-        let mut out: Vec<(u8, u8)> = Vec::new();
+    pub fn potential_bombs(&self) -> Result<Vec<(u16, u16)>, Error> {
+        let mut out: Vec<(u16, u16)> = Vec::new();
         for cell in self.grid.get_iter() {
             let (x, y) = cell.pos;
-            if let Some(cell) = self.grid.get_cell(x.into(), y.into()) {
-                if cell == Cell::Unknown {
-                    if self.grid.adj_number(x.into(), y.into())?.len() > 0 {
-                        out.push((x, y));
-                    }
-                }
+            if *cell.val == Cell::Unknown
+                && self.grid.neighbours(x, y).any(|(adj_x, adj_y)| {
+                    matches!(
+                        self.grid.get(adj_x.into(), adj_y.into()),
+                        Some(Cell::Number(1..))
+                    )
+                })
+            {
+                out.push((x, y));
             }
         }
 
         Ok(out)
     }
 
-    pub fn permute_solve_step(&mut self) -> Result<bool, String> {
-        // TODO: i can get rid of this clone by simply having a flag on Grid to
-        // represent that something has changed/progress has been made
-        let pre_board = self.grid.clone();
+    /// Partitions `potential_bombs` into independent components, where two cells are in the same
+    /// component if they are linked (directly or transitively) through a shared number cell.
+    pub fn frontier_components(
+        &self,
+        potential_bombs: &[(u16, u16)],
+    ) -> Result<Vec<Vec<(u16, u16)>>, Error> {
+        let mut number_to_cells: HashMap<(u16, u16), Vec<(u16, u16)>> = HashMap::new();
+        for &(x, y) in potential_bombs {
+            for number in self.grid.adj_number(x, y)? {
+                number_to_cells.entry(number).or_default().push((x, y));
+            }
+        }
 
-        let potential_bombs = self.potential_bombs()?;
+        let mut components = Vec::new();
+        let mut visited: HashSet<(u16, u16)> = HashSet::new();
+
+        for &start in potential_bombs {
+            if !visited.insert(start) {
+                continue;
+            }
 
-        if potential_bombs.len() > 127 {
-            return Err("too many potential bombs".to_string());
+            let mut component = Vec::new();
+            let mut stack = vec![start];
+
+            while let Some((x, y)) = stack.pop() {
+                component.push((x, y));
+
+                for number in self.grid.adj_number(x, y)? {
+                    for &linked in &number_to_cells[&number] {
+                        if visited.insert(linked) {
+                            stack.push(linked);
+                        }
+                    }
+                }
+            }
+
+            // keep cells in grid order so neighbouring cells are assigned one after another
+            component.sort_by_key(|&(x, y)| (y, x));
+            components.push(component);
+        }
+
+        Ok(components)
+    }
+
+    // every number cell next to any cell of the component, in (y, x) order. zeros are included so
+    // that no bomb is ever placed next to a revealed 0
+    fn component_numbers(&self, component: &[(u16, u16)]) -> Result<Vec<(u16, u16)>, Error> {
+        let mut numbers = Vec::new();
+        for &(x, y) in component {
+            for (adj_x, adj_y) in self.grid.neighbours(x, y) {
+                if let Some(Cell::Number(_)) = self.grid.get_cell(adj_x.into(), adj_y.into()) {
+                    numbers.push((adj_x, adj_y));
+                }
+            }
         }
+        numbers.sort_by_key(|&(x, y)| (y, x));
+        numbers.dedup();
 
-        println!("potential_bombs: {:?}", potential_bombs);
+        Ok(numbers)
+    }
+
+    fn compile_component(&self, component: &[(u16, u16)]) -> Result<CompiledComponent, Error> {
+        let index: HashMap<(u16, u16), usize> = component
+            .iter()
+            .enumerate()
+            .map(|(i, &pos)| (pos, i))
+            .collect();
 
-        let mut valid_patterns: Vec<u128> = Vec::new();
-        let end_pattern: u128 = 1u128 << potential_bombs.len();
+        let mut numbers = Vec::new();
+        for (x, y) in self.component_numbers(component)? {
+            let remaining = match self.flag_adj_grid.get_cell(x.into(), y.into()) {
+                Some(Some(num)) => num,
+                _ => {
+                    return Err(Error::InconsistentBoard(format!(
+                        "missing flag adj value at ({}, {})",
+                        x, y
+                    )));
+                }
+            };
 
-        println!("end_pattern: {:b} ({})", end_pattern, potential_bombs.len());
+            let mut cells: Vec<usize> = self
+                .grid
+                .adj_cells(x, y, Some(HashSet::from([Cell::Unknown])))?
+                .iter()
+                .filter_map(|pos| index.get(pos).copied())
+                .collect();
+            cells.sort_unstable();
 
-        // if more than 20 potential bomb locations, do not even attempt
-        if potential_bombs.len() > 20 {
-            return Err("too complex".to_string());
+            numbers.push((cells, remaining));
         }
 
-        for pattern in 1..end_pattern {
-            if self.is_valid_bomb_pattern(&potential_bombs, pattern)? {
-                valid_patterns.push(pattern);
+        Ok(CompiledComponent::new(component.len(), numbers))
+    }
+
+    pub fn tally_component(&self, component: &[(u16, u16)]) -> Result<ComponentTally, Error> {
+        let component = self.compile_component(component)?;
+        ComponentSearch::new(&component).run()
+    }
+
+    /// Simple rule for number cells whose component was too complex to search: if the number
+    /// needs no more bombs its unknown neighbours are safe, if it needs exactly as many bombs as it
+    /// has unknown neighbours they are all bombs.
+    fn simple_deductions(
+        &self,
+        cells: &[(u16, u16)],
+        deductions: &mut Deductions,
+    ) -> Result<(), Error> {
+        for &(x, y) in cells {
+            for (num_x, num_y) in self.grid.adj_number(x, y)? {
+                let Some(Some(remaining)) = self.flag_adj_grid.get_cell(num_x.into(), num_y.into())
+                else {
+                    continue;
+                };
+                let Some(Cell::Number(value)) = self.grid.get_cell(num_x.into(), num_y.into())
+                else {
+                    continue;
+                };
+                let unknown =
+                    self.grid
+                        .adj_cells(num_x, num_y, Some(HashSet::from([Cell::Unknown])))?;
+                let flags = usize::from(value - remaining);
+
+                let (rule, bomb) = if remaining == 0 {
+                    let rule = Rule::AllSafe {
+                        number: (num_x, num_y),
+                        value,
+                        flags,
+                    };
+                    (rule, false)
+                } else if remaining as usize == unknown.len() {
+                    let rule = Rule::AllBombs {
+                        number: (num_x, num_y),
+                        value,
+                        flags,
+                        unknown: unknown.len(),
+                    };
+                    (rule, true)
+                } else {
+                    continue;
+                };
+
+                for &cell in &unknown {
+                    deductions.explanations.push(Explanation {
+                        cell,
+                        bomb,
+                        rule: rule.clone(),
+                    });
+                }
+                if bomb {
+                    deductions.bombs.extend(unknown);
+                } else {
+                    deductions.safe.extend(unknown);
+                }
             }
         }
 
-        if valid_patterns.len() == 0 {
-            println!("no valid patterns");
-            return Ok(false);
+        Ok(())
+    }
+
+    /// Every unknown cell that is guaranteed to be a bomb or guaranteed to be safe, found by
+    /// searching every frontier component and combining them with the remaining bomb count. Only
+    /// the player's view (`grid`, `flag_adj_grid` and the bomb count) is used.
+    pub fn deductions(&self) -> Result<Deductions, Error> {
+        let mut deductions = Deductions::default();
+
+        let potential_bombs = self.potential_bombs()?;
+        let components = self.frontier_components(&potential_bombs)?;
+
+        let mut tallies = Vec::new();
+        for component in &components {
+            match self.tally_component(component) {
+                Ok(tally) => {
+                    deductions.valid_patterns.push(tally.total_solutions());
+                    tallies.push(Some(tally));
+                }
+                // too complex to count, which leaves the numbers around it to the simple rules
+                Err(Error::SolverLimit(_)) => {
+                    self.simple_deductions(component, &mut deductions)?;
+                    tallies.push(None);
+                }
+                Err(err) => return Err(err),
+            }
         }
 
-        println!("valid_patterns: {:?}", valid_patterns);
+        let frontier: HashSet<(u16, u16)> = potential_bombs.iter().copied().collect();
+        let interior: Vec<(u16, u16)> = self
+            .grid
+            .get_iter()
+            .filter(|cell| *cell.val == Cell::Unknown && !frontier.contains(&cell.pos))
+            .map(|cell| cell.pos)
+            .collect();
+
+        // the global bomb count can only be used when every component has been fully counted
+        let global = match (
+            tallies.iter().all(|tally| tally.is_some()),
+            self.remaining_bombs(),
+        ) {
+            (true, Some(remaining)) => {
+                let tallies: Vec<&ComponentTally> = tallies.iter().flatten().collect();
+                GlobalFeasibility::new(&tallies, remaining as usize, interior.len())
+            }
+            _ => None,
+        };
+
+        for (c, (component, tally)) in components.iter().zip(&tallies).enumerate() {
+            let Some(tally) = tally else { continue };
+            let feasible = |k: usize| match global {
+                Some(ref global) => global.components[c][k],
+                None => true,
+            };
 
-        let (flag_pattern, safe_pattern) =
-            calc_result_patterns(&potential_bombs, &valid_patterns, end_pattern);
+            let mut rule = None;
+            for (i, &pos) in component.iter().enumerate() {
+                let Some(bomb) = tally.certainty(i, feasible) else {
+                    continue;
+                };
 
-        for i in 0..potential_bombs.len() {
-            let (x, y) = potential_bombs[i];
+                if bomb {
+                    deductions.bombs.push(pos);
+                } else {
+                    deductions.safe.push(pos);
+                }
 
-            if ((flag_pattern >> i) & 0b1) == 1 {
-                self.place_flag(x, y)?;
+                if rule.is_none() {
+                    let valid_patterns = (0..tally.solutions.len())
+                        .filter(|&k| feasible(k))
+                        .map(|k| tally.solutions[k])
+                        .sum();
+                    rule = Some(Rule::Component {
+                        numbers: self.component_numbers(component)?,
+                        cells: component.len(),
+                        valid_patterns,
+                        bomb_count: valid_patterns != tally.total_solutions(),
+                    });
+                }
+                deductions.explanations.push(Explanation {
+                    cell: pos,
+                    bomb,
+                    rule: rule.clone().unwrap(),
+                });
             }
+        }
+
+        if let Some(global) = global
+            && !interior.is_empty()
+        {
+            let feasible: Vec<usize> = (0..global.interior.len())
+                .filter(|j| global.interior[*j])
+                .collect();
 
-            if ((safe_pattern >> i) & 0b1) == 0 {
-                self.flood_fill(x.into(), y.into())?;
+            let bomb = if feasible == [0] {
+                deductions.safe.extend(&interior);
+                Some(false)
+            } else if feasible == [interior.len()] {
+                deductions.bombs.extend(&interior);
+                Some(true)
+            } else {
+                None
+            };
+
+            if let Some(bomb) = bomb {
+                let remaining = self.remaining_bombs().unwrap_or_default();
+                deductions
+                    .explanations
+                    .extend(interior.iter().map(|&cell| Explanation {
+                        cell,
+                        bomb,
+                        rule: Rule::BombCount {
+                            remaining,
+                            interior: interior.len(),
+                        },
+                    }));
             }
         }
 
+        if deductions.is_empty() && tallies.iter().any(|tally| tally.is_none()) {
+            return Err(Error::SolverLimit(
+                "frontier components too complex to deduce anything".to_string(),
+            ));
+        }
+
+        deductions.normalise();
+
+        Ok(deductions)
+    }
+
+    pub fn permute_solve_step(&mut self) -> Result<bool, Error> {
+        // TODO: i can get rid of this clone by simply having a flag on Grid to
+        // represent that something has changed/progress has been made
+        let pre_board = self.grid.clone();
+
+        let deductions = self.deductions()?;
+        self.apply_deductions(&deductions)?;
+
         Ok(pre_board != self.grid)
     }
+
+    /// Flags every deduced bomb and reveals every deduced safe cell, recording their explanations.
+    pub fn apply_deductions(&mut self, deductions: &Deductions) -> Result<(), Error> {
+        for &(x, y) in &deductions.bombs {
+            self.place_flag(x, y)?;
+        }
+        for &(x, y) in &deductions.safe {
+            self.flood_fill(x.into(), y.into())?;
+        }
+        self.explain(deductions.explanations.iter().cloned());
+
+        Ok(())
+    }
+}
+
+/// Deduces which unknown cells of a player's view are guaranteed to be safe or guaranteed to be
+/// bombs, from nothing but the visible grid and the total number of bombs on the board.
+pub fn deduce(grid: &Grid<Cell>, num_bombs: u32) -> Result<Deductions, Error> {
+    GameBoard::from_view(grid.clone(), num_bombs, NoOracle)?.deductions()
 }
 
 #[cfg(test)]
 mod tests {
     use std::collections::HashMap;
 
+    use std::str::FromStr;
+    use std::time::Instant;
+
     use super::*;
+    use crate::fixtures::{get_count_safe_pre_str, get_count_safe_solved_str};
 
     fn get_solved_str() -> &'static str {
         "B  1  1  B  3  B  3  2  4  B  B  1  1  B  2  1 
@@ -209,6 +1209,382 @@ mod tests {
         0  0  0  0  1  1  1  0  0  0  0  1  1  1  1  F"
     }
 
+    fn get_components_solved_str() -> &'static str {
+        "0  1  B  1  0  0  1  B  1  0  0  1  B  1  0  0
+        0  1  1  1  0  0  1  1  1  0  0  1  1  1  0  0
+        0  0  0  0  0  0  0  0  0  0  0  0  0  0  0  0
+        0  0  0  0  0  0  0  0  0  0  0  0  0  0  0  0
+        0  0  0  0  0  0  0  0  0  0  0  0  0  0  0  0
+        0  0  0  0  0  0  0  0  0  0  0  0  0  0  0  0
+        0  0  0  0  0  0  0  0  1  1  1  0  0  0  0  0
+        0  0  0  0  0  1  1  1  1  B  1  0  0  0  0  0
+        0  0  0  0  0  1  B  1  1  1  1  0  0  0  0  0
+        0  0  0  0  0  1  1  1  0  0  0  0  0  0  0  0
+        0  0  0  0  0  0  0  0  0  0  0  0  0  0  0  0
+        0  0  0  0  0  0  0  0  0  0  0  0  0  0  0  0
+        0  0  0  0  0  0  0  0  0  0  0  0  0  0  0  0
+        0  0  0  0  0  0  0  0  0  0  0  0  0  0  0  0
+        0  0  0  1  1  1  0  0  0  1  1  1  0  0  0  0
+        0  0  0  1  B  1  0  0  0  1  B  1  0  0  0  0"
+    }
+
+    fn get_components_pre_str() -> &'static str {
+        "?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?
+        0  1  1  1  0  0  1  1  1  0  0  1  1  1  0  0
+        0  0  0  0  0  0  0  0  0  0  0  0  0  0  0  0
+        0  0  0  0  0  0  0  0  0  0  0  0  0  0  0  0
+        0  0  0  0  0  0  0  0  0  0  0  0  0  0  0  0
+        0  0  0  0  0  0  0  0  0  0  0  0  0  0  0  0
+        0  0  0  0  0  0  0  0  1  1  1  0  0  0  0  0
+        0  0  0  0  0  ?  ?  ?  ?  ?  ?  0  0  0  0  0
+        0  0  0  0  0  ?  ?  ?  ?  ?  ?  0  0  0  0  0
+        0  0  0  0  0  1  1  1  0  0  0  0  0  0  0  0
+        0  0  0  0  0  0  0  0  0  0  0  0  0  0  0  0
+        0  0  0  0  0  0  0  0  0  0  0  0  0  0  0  0
+        0  0  0  0  0  0  0  0  0  0  0  0  0  0  0  0
+        0  0  0  0  0  0  0  0  0  0  0  0  0  0  0  0
+        0  0  0  1  1  1  0  0  0  1  1  1  0  0  0  0
+        ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?"
+    }
+
+    fn get_pair_solved_str() -> &'static str {
+        "1  B  2  B  1
+        1  1  2  1  1
+        0  0  0  0  0"
+    }
+
+    fn get_pair_pre_str() -> &'static str {
+        "?  ?  ?  ?  ?
+        1  1  2  1  1
+        0  0  0  0  0"
+    }
+
+    fn get_count_bombs_solved_str() -> &'static str {
+        "B  2  0  0  0
+        B  4  1  0  0
+        B  B  1  0  0
+        B  4  1  0  0
+        B  2  0  0  0"
+    }
+
+    fn get_count_bombs_pre_str() -> &'static str {
+        "?  ?  0  0  0
+        ?  ?  1  0  0
+        ?  ?  1  0  0
+        ?  ?  1  0  0
+        ?  ?  0  0  0"
+    }
+
+    fn assert_consistent(board: &GameBoard) {
+        for cell in board.grid.get_iter() {
+            let (x, y) = cell.pos;
+            let solved = board.oracle.get_cell(x.into(), y.into()).unwrap();
+            match cell.val {
+                Cell::Flag => assert_eq!(solved, Cell::Bomb, "bad flag at ({}, {})", x, y),
+                Cell::Number(_) => assert_eq!(*cell.val, solved, "bad reveal at ({}, {})", x, y),
+                _ => (),
+            }
+        }
+    }
+
+    fn get_expert_solved_str() -> &'static str {
+        "2  B  4  B  1  0  1  B  1  1  B  2  2  2  3  2  2  1  1  0  0  0  0  0  0  0  0  0  1  1
+        3  B  B  3  2  2  2  2  2  2  3  B  2  B  B  B  4  B  2  1  2  2  1  1  1  1  1  2  3  B
+        2  B  5  4  B  3  B  2  2  B  2  1  2  2  4  B  5  B  3  2  B  B  3  2  B  1  1  B  B  2
+        1  2  B  B  3  B  4  B  3  1  2  2  2  1  1  2  B  3  3  B  4  B  B  2  1  2  2  3  2  1
+        0  1  2  2  2  1  3  B  2  0  1  B  B  3  1  1  2  4  B  3  2  2  2  1  0  1  B  1  1  1
+        1  2  2  2  1  2  2  2  2  1  3  4  B  B  2  0  1  B  B  3  0  0  0  0  0  1  1  1  1  B
+        2  B  B  2  B  2  B  1  1  B  2  B  4  B  3  1  2  4  B  2  0  0  0  1  1  1  0  0  1  1
+        3  B  4  2  1  2  1  1  1  1  2  1  2  2  B  1  1  B  4  4  2  1  0  1  B  2  2  3  2  1
+        2  B  2  0  0  0  0  0  0  0  0  0  1  2  2  2  2  4  B  B  B  1  0  1  1  2  B  B  B  1
+        1  2  2  1  0  0  0  0  0  1  1  1  1  B  2  2  B  3  B  4  2  2  2  2  1  1  2  3  3  2
+        0  1  B  3  3  2  1  0  1  2  B  1  1  2  B  2  2  4  3  2  0  1  B  B  1  1  2  2  2  B
+        1  3  4  B  B  B  1  0  2  B  3  1  0  1  1  2  2  B  B  1  0  1  2  2  1  1  B  B  2  1
+        1  B  B  3  3  2  2  1  3  B  2  0  0  0  0  1  B  4  3  2  1  1  0  0  1  2  3  2  1  0
+        1  2  3  2  1  0  2  B  3  1  1  0  0  0  0  1  2  B  2  3  B  2  0  0  1  B  3  2  2  1
+        0  1  3  B  2  1  3  B  2  1  1  1  0  0  0  0  2  4  B  4  B  2  0  0  1  2  B  B  3  B
+        0  1  B  B  2  1  B  2  1  1  B  1  0  0  0  0  1  B  B  3  1  1  0  0  0  1  3  B  3  1"
+    }
+
+    fn get_expert_pre_str() -> &'static str {
+        "?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?
+        ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?
+        ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?
+        ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?
+        ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?
+        ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?
+        ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?  ?
+        3  ?  4  2  1  2  1  1  1  1  2  1  2  2  ?  1  1  ?  4  4  2  1  0  1  ?  2  2  3  2  1
+        2  ?  2  0  0  0  0  0  0  0  0  0  1  2  2  2  2  4  ?  ?  ?  1  0  1  1  2  ?  ?  ?  1
+        1  2  2  1  0  0  0  0  0  1  1  1  1  ?  2  2  ?  3  ?  4  2  2  2  2  1  1  2  3  3  2
+        0  1  ?  3  3  2  1  0  1  2  ?  1  1  2  ?  2  2  4  3  2  0  1  ?  ?  1  1  2  2  2  ?
+        1  3  4  ?  ?  ?  1  0  2  ?  3  1  0  1  1  2  2  ?  ?  1  0  1  2  2  1  1  ?  ?  2  1
+        1  ?  ?  3  3  2  2  1  3  ?  2  0  0  0  0  1  ?  4  3  2  1  1  0  0  1  2  3  2  1  0
+        1  2  3  2  1  0  2  ?  3  1  1  0  0  0  0  1  2  ?  2  3  ?  2  0  0  1  ?  3  2  2  1
+        0  1  3  ?  2  1  3  ?  2  1  1  1  0  0  0  0  2  4  ?  4  ?  2  0  0  1  2  ?  ?  3  ?
+        0  1  ?  ?  2  1  ?  2  1  1  ?  1  0  0  0  0  1  ?  ?  3  1  1  0  0  0  1  3  ?  3  1"
+    }
+
+    #[test]
+    pub fn frontier_components_1() {
+        let board =
+            GameBoard::from_str(get_components_solved_str(), get_components_pre_str()).unwrap();
+
+        let potential_bombs = board.potential_bombs().unwrap();
+        assert!(potential_bombs.len() > 20);
+
+        let components = board.frontier_components(&potential_bombs).unwrap();
+        let mut sizes: Vec<usize> = components.iter().map(|c| c.len()).collect();
+        sizes.sort();
+
+        assert_eq!(sizes, vec![4, 4, 5, 5, 5, 5, 5]);
+        assert_eq!(
+            components.iter().map(|c| c.len()).sum::<usize>(),
+            potential_bombs.len()
+        );
+    }
+
+    #[test]
+    fn permute_components_1() {
+        let mut board =
+            GameBoard::from_str(get_components_solved_str(), get_components_pre_str()).unwrap();
+
+        let progress = board.permute_solve_step().unwrap();
+        println!("{}", board);
+
+        assert!(progress);
+        assert_consistent(&board);
+        assert_eq!(board.grid.get_cell(9, 8), Some(Cell::Flag));
+        assert_eq!(board.grid.get_cell(6, 7), Some(Cell::Flag));
+    }
+
+    #[test]
+    fn compile_component_1() {
+        let board = GameBoard::from_str(get_pair_solved_str(), get_pair_pre_str()).unwrap();
+
+        let component = board
+            .compile_component(&[(0, 2), (1, 2), (2, 2), (3, 2), (4, 2)])
+            .unwrap();
+        let masks: Vec<Vec<(usize, u64)>> = component
+            .constraints
+            .iter()
+            .map(|constraint| constraint.mask.clone())
+            .collect();
+        assert_eq!(
+            masks,
+            vec![
+                vec![(0, 0b00011)],
+                vec![(0, 0b00111)],
+                vec![(0, 0b01110)],
+                vec![(0, 0b11100)],
+                vec![(0, 0b11000)]
+            ]
+        );
+        assert_eq!(component.touching[0], vec![(0, 1), (1, 2)]);
+        assert_eq!(component.touching[4], vec![(3, 0), (4, 0)]);
+
+        // the numbers need 1 + 1 + 2 + 1 + 1 bombs between them, more than the 5 cells can hold
+        let tally = ComponentSearch::new(&component).run().unwrap();
+        assert_eq!(tally.solutions, vec![0, 0, 1, 0, 0, 0]);
+        assert_eq!(tally.bomb_counts[2], vec![0, 1, 0, 1, 0]);
+        assert!(tally.bomb_counts[3].is_empty());
+    }
+
+    #[test]
+    fn tally_long_component_1() {
+        // a single component of 60002 cells above a row of 1s, which used to overflow the stack
+        // with one recursive call per cell
+        let width = 60_002;
+        let mut solved = Grid::new(width, 2, Cell::Number(0));
+        for x in (1..width).step_by(3) {
+            solved.set_cell(x.into(), 1, Cell::Bomb).unwrap();
+        }
+        let mut board = GameBoard::from(solved).unwrap();
+        for x in 0..width {
+            assert!(board.reveal(x.into(), 0).unwrap());
+        }
+
+        let potential_bombs = board.potential_bombs().unwrap();
+        let components = board.frontier_components(&potential_bombs).unwrap();
+        assert_eq!(components.len(), 1);
+
+        // the bombs are either above every x = 1 (mod 3) or every x = 0 (mod 3)
+        let tally = board.tally_component(&components[0]).unwrap();
+        assert_eq!(tally.total_solutions(), 2);
+        assert_eq!(tally.solutions[20_001], 2);
+        assert_eq!(tally.bomb_count(20_001, 0), 1);
+        assert_eq!(tally.bomb_count(20_001, 1), 1);
+        assert_eq!(tally.bomb_count(20_001, 2), 0);
+    }
+
+    #[test]
+    fn permute_wide_frontier_1() {
+        let mut board = GameBoard::from_str(get_expert_solved_str(), get_expert_pre_str()).unwrap();
+
+        let potential_bombs = board.potential_bombs().unwrap();
+        let components = board.frontier_components(&potential_bombs).unwrap();
+        assert!(potential_bombs.len() > 40);
+        assert!(components.iter().any(|c| c.len() > 20));
+
+        let progress = board.permute_solve_step().unwrap();
+        println!("{}", board);
+
+        assert!(progress);
+        assert_consistent(&board);
+    }
+
+    #[test]
+    fn pair_1() {
+        // a 1-2-1 against the top wall, which no single number can resolve
+        let mut board = GameBoard::from_str(get_pair_solved_str(), get_pair_pre_str()).unwrap();
+        assert!(!board.clone().simple_solve_step().unwrap());
+
+        let deductions = board.pair_deductions().unwrap();
+        assert_eq!(deductions.bombs, vec![(1, 2), (3, 2)]);
+        assert_eq!(deductions.safe, vec![(0, 2), (2, 2), (4, 2)]);
+        assert!(deductions.valid_patterns.is_empty());
+
+        assert!(board.pair_solve_step().unwrap());
+        assert_consistent(&board);
+        assert!(board.is_solved().unwrap());
+    }
+
+    #[test]
+    fn pair_2() {
+        let mut board = GameBoard::from_str(get_expert_solved_str(), get_expert_pre_str()).unwrap();
+
+        let mut progress = true;
+        while progress {
+            progress = board.simple_solve_step().unwrap() || board.pair_solve_step().unwrap();
+            assert_consistent(&board);
+        }
+        println!("{}", board);
+
+        assert!(
+            board
+                .explanations()
+                .iter()
+                .any(|explanation| matches!(explanation.rule, Rule::Pair { .. }))
+        );
+    }
+
+    #[test]
+    fn linear_1() {
+        let mut board = GameBoard::from_str(get_pair_solved_str(), get_pair_pre_str()).unwrap();
+
+        let deductions = board.linear_deductions().unwrap();
+        assert_eq!(deductions.bombs, vec![(1, 2), (3, 2)]);
+        assert_eq!(deductions.safe, vec![(0, 2), (2, 2), (4, 2)]);
+
+        assert!(board.linear_solve_step().unwrap());
+        assert_consistent(&board);
+        assert!(board.is_solved().unwrap());
+    }
+
+    #[test]
+    fn linear_2() {
+        // the wide frontier of the expert board is a single system, however many cells it has
+        let mut board = GameBoard::from_str(get_expert_solved_str(), get_expert_pre_str()).unwrap();
+        assert!(board.potential_bombs().unwrap().len() > 40);
+
+        let mut progress = true;
+        while progress {
+            progress = board.simple_solve_step().unwrap()
+                || board.pair_solve_step().unwrap()
+                || board.linear_solve_step().unwrap();
+            assert_consistent(&board);
+        }
+        println!("{}", board);
+
+        for explanation in board.explanations() {
+            let (x, y) = explanation.cell;
+            let solved = board.oracle.get_cell(x.into(), y.into()).unwrap();
+            assert_eq!(explanation.bomb, solved == Cell::Bomb, "{}", explanation);
+        }
+    }
+
+    #[test]
+    fn sat_1() {
+        let board =
+            GameBoard::from_str(get_components_solved_str(), get_components_pre_str()).unwrap();
+
+        let sat = board.sat_deductions().unwrap();
+        let permute = board.deductions().unwrap();
+        assert_eq!(sat.safe, permute.safe);
+        assert_eq!(sat.bombs, permute.bombs);
+    }
+
+    #[test]
+    fn sat_2() {
+        let mut board = GameBoard::from_str(get_expert_solved_str(), get_expert_pre_str()).unwrap();
+
+        let mut progress = true;
+        while progress {
+            progress = board.simple_solve_step().unwrap() || board.sat_solve_step().unwrap();
+            assert_consistent(&board);
+        }
+        println!("{}", board);
+    }
+
+    #[test]
+    fn permute_bomb_count_1() {
+        // the frontier holds the only bomb, so the interior column must be safe
+        let mut board =
+            GameBoard::from_str(get_count_safe_solved_str(), get_count_safe_pre_str()).unwrap();
+
+        let progress = board.permute_solve_step().unwrap();
+        println!("{}", board);
+
+        assert!(progress);
+        assert_consistent(&board);
+        assert!(board.is_solved().unwrap());
+        for y in 0..5 {
+            assert_ne!(board.grid.get_cell(0, y), Some(Cell::Unknown));
+        }
+    }
+
+    #[test]
+    fn permute_bomb_count_2() {
+        // 6 bombs, 1 on the frontier, so all 5 interior cells must be bombs
+        let mut board =
+            GameBoard::from_str(get_count_bombs_solved_str(), get_count_bombs_pre_str()).unwrap();
+
+        let progress = board.permute_solve_step().unwrap();
+        println!("{}", board);
+
+        assert!(progress);
+        assert_consistent(&board);
+        assert_eq!(board.remaining_bombs(), Some(0));
+        for y in 0..5 {
+            assert_eq!(board.grid.get_cell(0, y), Some(Cell::Flag));
+        }
+    }
+
+    #[test]
+    fn deduce_1() {
+        let grid = Grid::from_str(get_count_safe_pre_str()).unwrap();
+
+        let deductions = deduce(&grid, 1).unwrap();
+
+        assert_eq!(deductions.bombs, vec![(1, 2)]);
+        assert_eq!(deductions.safe.len(), 9);
+        assert!(!deductions.safe.contains(&(1, 2)));
+    }
+
+    #[test]
+    fn deduce_2() {
+        // with 6 bombs the interior column must be all bombs instead
+        let grid = Grid::from_str(get_count_safe_pre_str()).unwrap();
+
+        let deductions = deduce(&grid, 6).unwrap();
+
+        assert_eq!(
+            deductions.bombs,
+            vec![(0, 0), (0, 1), (0, 2), (1, 2), (0, 3), (0, 4)]
+        );
+        assert_eq!(deductions.safe, vec![(1, 0), (1, 1), (1, 3), (1, 4)]);
+    }
+
     #[test]
     #[ignore]
     fn permute_1() {
@@ -217,6 +1593,10 @@ mod tests {
         // AFTER FLAG ADJ GRID OPTIMIZATION:
         // this takes ~3s for me in debug build
         // this takes ~1s for me in release build (wowie)
+        // AFTER SPLITTING INTO FRONTIER COMPONENTS:
+        // this takes ~3ms in release build
+        // AFTER BACKTRACKING:
+        // this takes <1ms in release build
         //
         // potential_bombs: [(13, 14), (14, 14), (15, 14), (0, 15), (1, 15), (2, 15), (3, 15), (4, 15), (5, 15), (6, 15), (7, 15), (9, 15), (10, 15), (11, 15), (12, 15), (13, 15)]
         // end_pattern: 10000000000000000 (16)
@@ -242,14 +1622,97 @@ mod tests {
 
         println!(
             "\nsolved:\n{}\npre solved:\n{}\npost solved:\n{}\nexpected:\n{}",
-            pre_board.solved_grid,
+            pre_board.oracle,
             pre_solve.grid,
             pre_board.grid,
             post_board.grid.to_string(Some(diff_map))
         );
 
-        if pre_board.grid != post_board.grid {
-            assert!(false, "solved board != expected solved board");
+        assert!(
+            pre_board.grid == post_board.grid,
+            "solved board != expected solved board"
+        );
+    }
+
+    #[test]
+    #[ignore]
+    fn permute_bench_1() {
+        // validates the same bomb patterns of every frontier component of the permute_1 fixture
+        // twice: the way `is_valid_bomb_pattern` used to (cloning the board, placing each flag and
+        // reading the flag adj values of the numbers around the component) and with popcounts
+        // against the compiled bitmasks, 1000 times over. only the first 12 cells of a component
+        // are varied, which covers every pattern of this fixture. run with
+        // `cargo test --release permute_bench_1 -- --ignored --nocapture`
+        //
+        // CLONING THE BOARD PER PATTERN:
+        // this takes ~300ms in release build
+        // WITH BITMASKS PER NUMBER (INCLUDING COMPILING THEM):
+        // this takes ~25ms in release build
+        let board = GameBoard::from_str(get_solved_str(), get_pre_str()).unwrap();
+        let potential_bombs = board.potential_bombs().unwrap();
+        let components = board.frontier_components(&potential_bombs).unwrap();
+        let patterns = |component: &[(u16, u16)]| 0..1u64 << component.len().min(12);
+
+        let by_cloning = || {
+            let mut valid = Vec::new();
+            for component in &components {
+                let numbers = board.component_numbers(component).unwrap();
+                for pattern in patterns(component) {
+                    let mut flagged = board.clone();
+                    let placed = component
+                        .iter()
+                        .enumerate()
+                        .filter(|&(i, _)| (pattern >> i) & 1 == 1)
+                        .all(|(_, &(x, y))| flagged.place_flag(x, y).is_ok());
+
+                    valid.push(
+                        placed
+                            && numbers.iter().all(|&(x, y)| {
+                                flagged.flag_adj_grid.get_cell(x.into(), y.into()) == Some(Some(0))
+                            }),
+                    );
+                }
+            }
+            valid
+        };
+        let by_bitmasks = || {
+            let mut valid = Vec::new();
+            for component in &components {
+                let compiled = board.compile_component(component).unwrap();
+                let mut bitboard = Bitboard::new(component.len());
+                for pattern in patterns(component) {
+                    bitboard.words[0] = pattern;
+                    valid.push(
+                        compiled
+                            .constraints
+                            .iter()
+                            .all(|constraint| constraint.satisfiable(&bitboard, 0)),
+                    );
+                }
+            }
+            valid
+        };
+
+        let valid = by_cloning();
+        assert_eq!(valid, by_bitmasks());
+        assert!(valid.contains(&true));
+
+        let start = Instant::now();
+        for _ in 0..1000 {
+            by_cloning();
+        }
+        let cloned = start.elapsed();
+        let start = Instant::now();
+        for _ in 0..1000 {
+            by_bitmasks();
         }
+        let bitmasks = start.elapsed();
+
+        println!(
+            "{} patterns 1000 times, cloning: {:?}, bitmasks: {:?}",
+            valid.len(),
+            cloned,
+            bitmasks
+        );
     }
 }
@@ -1,53 +1,753 @@
-use std::time::Instant;
+use std::{
+    str::FromStr,
+    time::{Duration, Instant},
+};
 
-use crate::board::GameBoard;
+use serde::Serialize;
 
-pub fn strategy_simple_permute(
-    board: GameBoard,
-    starting_cell: (u8, u8),
-) -> Result<Option<Vec<Vec<&'static str>>>, String> {
-    let mut step_summary: Vec<Vec<&str>> = Vec::new();
+use crate::{
+    Cell,
+    board::{GameBoard, RevealOracle},
+    error::Error,
+    explain::Explanation,
+};
 
-    let mut game_board = board.clone();
-    println!("starting: {:?}", starting_cell);
-    game_board.flood_fill(starting_cell.0.into(), starting_cell.1.into())?;
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub enum GameResult {
+    Win,
+    Loss,
+    /// no technique could make progress and the strategy is not allowed to guess
+    Stuck,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct StrategyResult {
+    pub result: GameResult,
+    pub step_summary: Vec<Step>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Technique {
+    /// `simple_solve_step`
+    Basic,
+    /// `pair_solve_step`
+    Pair,
+    /// `linear_solve_step`
+    Linear,
+    /// `sat_solve_step`
+    Sat,
+    /// `deductions` over every frontier component
+    Permute,
+    /// revealing the cell least likely to be a bomb
+    Guess,
+}
+
+/// Everything that happened during a single step of a strategy.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Step {
+    /// the technique that made progress, `None` if every technique tried failed
+    pub technique: Option<Technique>,
+    /// every technique tried this step, in order
+    pub tried: Vec<Technique>,
+    /// cells revealed this step, including those opened by flood fill
+    pub revealed: Vec<(u16, u16)>,
+    pub flagged: Vec<(u16, u16)>,
+    pub elapsed: Duration,
+    /// the number of unknown cells next to a revealed number at the start of the step
+    pub frontier_size: usize,
+    /// the number of valid bomb patterns of each frontier component, empty unless permute ran
+    pub valid_patterns: Vec<u64>,
+    /// why each cell flagged or revealed by a deduction this step was deduced
+    pub explanations: Vec<Explanation>,
+    pub guess: Option<Guess>,
+}
+
+/// Options shared by every strategy, set by name through `Strategy::configure`.
+#[derive(Clone, Copy, Debug)]
+pub struct StrategyConfig {
+    /// print the board after every step
+    pub verbose: bool,
+    /// give up (`GameResult::Stuck`) after this many steps
+    pub max_steps: Option<usize>,
+}
+
+impl Default for StrategyConfig {
+    fn default() -> Self {
+        StrategyConfig {
+            verbose: true,
+            max_steps: None,
+        }
+    }
+}
+
+impl StrategyConfig {
+    fn set(&mut self, option: &str, value: &str) -> Result<(), Error> {
+        match option {
+            "verbose" => self.verbose = parse_option(option, value)?,
+            "max_steps" => self.max_steps = Some(parse_option(option, value)?),
+            _ => return Err(Error::Config(format!("unknown option `{}`", option))),
+        }
+
+        Ok(())
+    }
+}
+
+fn parse_option<T: FromStr>(option: &str, value: &str) -> Result<T, Error> {
+    value
+        .parse()
+        .map_err(|_| Error::Config(format!("invalid value for `{}`: `{}`", option, value)))
+}
+
+pub trait Strategy<O: RevealOracle> {
+    /// the name the strategy is registered under, used to select it from the CLI
+    fn name(&self) -> &'static str;
+
+    fn description(&self) -> &'static str;
+
+    /// Sets a single option by name, eg. `configure("max_steps", "20")`.
+    fn configure(&mut self, option: &str, value: &str) -> Result<(), Error>;
+
+    /// Plays the board from `starting_cell` until it is won, lost, or the strategy gets stuck.
+    fn run(&self, board: GameBoard<O>, starting_cell: (u16, u16)) -> Result<StrategyResult, Error>;
+}
+
+/// Only uses `simple_solve_step`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct BasicStrategy {
+    pub config: StrategyConfig,
+}
+
+/// Uses `simple_solve_step`, falling back to `pair_solve_step` when it gets stuck.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PairStrategy {
+    pub config: StrategyConfig,
+}
+
+/// Uses `simple_solve_step`, `pair_solve_step`, then `linear_solve_step`, each only when the
+/// previous ones get stuck.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct LinearStrategy {
+    pub config: StrategyConfig,
+}
+
+/// Like `PermuteStrategy`, with `sat_solve_step` in place of `permute_solve_step`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SatStrategy {
+    pub config: StrategyConfig,
+}
+
+/// Uses `simple_solve_step`, `pair_solve_step`, `linear_solve_step`, then `permute_solve_step`,
+/// each only when the previous ones get stuck.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PermuteStrategy {
+    pub config: StrategyConfig,
+}
+
+/// Like `PermuteStrategy`, but reveals the cell least likely to be a bomb when no cell is known to be
+/// safe.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct GuessStrategy {
+    pub config: StrategyConfig,
+    /// give up (`GameResult::Stuck`) rather than make more than this many guesses
+    pub max_guesses: Option<usize>,
+}
+
+impl<O: RevealOracle> Strategy<O> for BasicStrategy {
+    fn name(&self) -> &'static str {
+        "basic"
+    }
+
+    fn description(&self) -> &'static str {
+        "single cell deductions only"
+    }
+
+    fn configure(&mut self, option: &str, value: &str) -> Result<(), Error> {
+        self.config.set(option, value)
+    }
+
+    fn run(&self, board: GameBoard<O>, starting_cell: (u16, u16)) -> Result<StrategyResult, Error> {
+        play(board, starting_cell, &self.config, &[Technique::Basic], 0)
+    }
+}
+
+impl<O: RevealOracle> Strategy<O> for PairStrategy {
+    fn name(&self) -> &'static str {
+        "pair"
+    }
+
+    fn description(&self) -> &'static str {
+        "single cell deductions, then overlapping pairs of numbers"
+    }
+
+    fn configure(&mut self, option: &str, value: &str) -> Result<(), Error> {
+        self.config.set(option, value)
+    }
+
+    fn run(&self, board: GameBoard<O>, starting_cell: (u16, u16)) -> Result<StrategyResult, Error> {
+        let techniques = [Technique::Basic, Technique::Pair];
+        play(board, starting_cell, &self.config, &techniques, 0)
+    }
+}
 
-    println!("= init =======================================");
-    println!("{}", game_board);
+impl<O: RevealOracle> Strategy<O> for LinearStrategy {
+    fn name(&self) -> &'static str {
+        "linear"
+    }
 
-    step_summary.clear();
-    let mut i = 0;
-    let mut solved = false;
-    let mut progress = true;
+    fn description(&self) -> &'static str {
+        "single cell deductions, pairs, then row reducing the frontier"
+    }
+
+    fn configure(&mut self, option: &str, value: &str) -> Result<(), Error> {
+        self.config.set(option, value)
+    }
+
+    fn run(&self, board: GameBoard<O>, starting_cell: (u16, u16)) -> Result<StrategyResult, Error> {
+        let techniques = [Technique::Basic, Technique::Pair, Technique::Linear];
+        play(board, starting_cell, &self.config, &techniques, 0)
+    }
+}
+
+impl<O: RevealOracle> Strategy<O> for PermuteStrategy {
+    fn name(&self) -> &'static str {
+        "permute"
+    }
+
+    fn description(&self) -> &'static str {
+        "single cell deductions, pairs, row reduction, then frontier permutations"
+    }
+
+    fn configure(&mut self, option: &str, value: &str) -> Result<(), Error> {
+        self.config.set(option, value)
+    }
 
-    while !solved && progress {
-        println!("= {} =======================================", i + 1);
+    fn run(&self, board: GameBoard<O>, starting_cell: (u16, u16)) -> Result<StrategyResult, Error> {
+        let techniques = [
+            Technique::Basic,
+            Technique::Pair,
+            Technique::Linear,
+            Technique::Permute,
+        ];
+        play(board, starting_cell, &self.config, &techniques, 0)
+    }
+}
+
+impl<O: RevealOracle> Strategy<O> for SatStrategy {
+    fn name(&self) -> &'static str {
+        "sat"
+    }
+
+    fn description(&self) -> &'static str {
+        "single cell deductions, pairs, row reduction, then a SAT solver over the frontier"
+    }
+
+    fn configure(&mut self, option: &str, value: &str) -> Result<(), Error> {
+        self.config.set(option, value)
+    }
+
+    fn run(&self, board: GameBoard<O>, starting_cell: (u16, u16)) -> Result<StrategyResult, Error> {
+        let techniques = [
+            Technique::Basic,
+            Technique::Pair,
+            Technique::Linear,
+            Technique::Sat,
+        ];
+        play(board, starting_cell, &self.config, &techniques, 0)
+    }
+}
+
+impl<O: RevealOracle> Strategy<O> for GuessStrategy {
+    fn name(&self) -> &'static str {
+        "guess"
+    }
+
+    fn description(&self) -> &'static str {
+        "permutations, then guessing the safest cell by bomb probability"
+    }
+
+    fn configure(&mut self, option: &str, value: &str) -> Result<(), Error> {
+        match option {
+            "max_guesses" => self.max_guesses = Some(parse_option(option, value)?),
+            _ => self.config.set(option, value)?,
+        }
+
+        Ok(())
+    }
+
+    fn run(&self, board: GameBoard<O>, starting_cell: (u16, u16)) -> Result<StrategyResult, Error> {
+        let techniques = [
+            Technique::Basic,
+            Technique::Pair,
+            Technique::Linear,
+            Technique::Permute,
+            Technique::Guess,
+        ];
+        let max_guesses = self.max_guesses.unwrap_or(usize::MAX);
+        play(board, starting_cell, &self.config, &techniques, max_guesses)
+    }
+}
+
+/// Every available strategy with its default configuration, in order of increasing strength.
+pub fn registry<O: RevealOracle>() -> Vec<Box<dyn Strategy<O>>> {
+    vec![
+        Box::new(BasicStrategy::default()),
+        Box::new(PairStrategy::default()),
+        Box::new(LinearStrategy::default()),
+        Box::new(PermuteStrategy::default()),
+        Box::new(SatStrategy::default()),
+        Box::new(GuessStrategy::default()),
+    ]
+}
+
+pub fn strategy_by_name<O: RevealOracle>(name: &str) -> Result<Box<dyn Strategy<O>>, Error> {
+    registry()
+        .into_iter()
+        .find(|strategy| strategy.name() == name)
+        .ok_or(Error::Config(format!("unknown strategy `{}`", name)))
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub struct Guess {
+    pub pos: (u16, u16),
+    pub probability: f64,
+}
+
+/// Picks the unknown cell least likely to be a bomb. Ties are broken by preferring cells with fewer
+/// neighbours (corners, then edges) as they are more likely to open up an area, then by preferring
+/// cells next to revealed numbers as they tell us more about the existing frontier. Without exact
+/// probabilities (eg. no total bomb count) the estimates from the numbers are used instead.
+fn pick_guess<O: RevealOracle>(board: &GameBoard<O>) -> Result<Option<Guess>, Error> {
+    let probabilities = match board.bomb_probabilities() {
+        Ok(probabilities) => probabilities,
+        Err(Error::SolverLimit(_)) => board.estimated_probabilities()?,
+        Err(err) => return Err(err),
+    };
+
+    let mut best: Option<(Guess, (usize, usize))> = None;
+    for cell in board.grid.get_iter() {
+        if *cell.val != Cell::Unknown {
+            continue;
+        }
+
+        let (x, y) = cell.pos;
+        let probability = probabilities.get_cell(x.into(), y.into()).unwrap_or(1.0);
+        let tie_break = (
+            board.grid.neighbours(x, y).count(),
+            usize::from(board.grid.adj_number(x, y)?.is_empty()),
+        );
+
+        let is_better = match best {
+            None => true,
+            Some((best_guess, best_tie_break)) => {
+                if (probability - best_guess.probability).abs() > 1e-9 {
+                    probability < best_guess.probability
+                } else {
+                    tie_break < best_tie_break
+                }
+            }
+        };
+
+        if is_better {
+            best = Some((
+                Guess {
+                    pos: (x, y),
+                    probability,
+                },
+                tie_break,
+            ));
+        }
+    }
+
+    Ok(best.map(|(guess, _)| guess))
+}
+
+fn play<O: RevealOracle>(
+    board: GameBoard<O>,
+    starting_cell: (u16, u16),
+    config: &StrategyConfig,
+    techniques: &[Technique],
+    max_guesses: usize,
+) -> Result<StrategyResult, Error> {
+    let mut step_summary: Vec<Step> = Vec::new();
+    let finish = |result, step_summary| {
+        Ok(StrategyResult {
+            result,
+            step_summary,
+        })
+    };
+
+    let mut game_board = board;
+    if config.verbose {
+        println!("starting: {:?}", starting_cell);
+    }
+    if !game_board.reveal(starting_cell.0.into(), starting_cell.1.into())? {
+        return finish(GameResult::Loss, step_summary);
+    }
+
+    if config.verbose {
+        println!("= init =======================================");
+        println!("{}", game_board);
+    }
+
+    let mut guesses = 0;
+    let mut solved = game_board.is_solved()?;
+
+    while !solved {
+        if config
+            .max_steps
+            .is_some_and(|max_steps| step_summary.len() >= max_steps)
+        {
+            return finish(GameResult::Stuck, step_summary);
+        }
+
+        if config.verbose {
+            println!(
+                "= {} =======================================",
+                step_summary.len() + 1
+            );
+        }
         let start_time = Instant::now();
-        step_summary.push(vec!["basic"]);
-        progress = game_board.simple_solve_step()?;
+        let pre_board = game_board.grid.clone();
+        let mut step = Step {
+            technique: None,
+            tried: Vec::new(),
+            revealed: Vec::new(),
+            flagged: Vec::new(),
+            elapsed: Duration::ZERO,
+            frontier_size: game_board.potential_bombs()?.len(),
+            valid_patterns: Vec::new(),
+            explanations: Vec::new(),
+            guess: None,
+        };
+
+        // techniques are tried in order until one of them makes progress
+        let mut hit_bomb = false;
+        for &technique in techniques {
+            if technique == Technique::Guess && guesses >= max_guesses {
+                continue;
+            }
+            step.tried.push(technique);
+
+            let progress = match technique {
+                Technique::Basic => game_board.simple_solve_step(),
+                Technique::Pair => game_board.pair_solve_step(),
+                Technique::Linear => game_board.linear_solve_step(),
+                Technique::Sat => game_board.sat_solve_step(),
+                Technique::Permute => game_board.deductions().and_then(|deductions| {
+                    game_board.apply_deductions(&deductions)?;
+                    step.valid_patterns = deductions.valid_patterns;
+
+                    Ok(pre_board != game_board.grid)
+                }),
+                Technique::Guess => {
+                    let Some(guess) = pick_guess(&game_board)? else {
+                        continue;
+                    };
+
+                    let (x, y) = guess.pos;
+                    if config.verbose {
+                        println!(
+                            "guessing ({}, {}) with p(bomb) = {:.3}",
+                            x, y, guess.probability
+                        );
+                    }
+
+                    guesses += 1;
+                    step.guess = Some(guess);
+                    hit_bomb = !game_board.reveal(x.into(), y.into())?;
+                    if hit_bomb && config.verbose {
+                        println!("hit a bomb at ({}, {})", x, y);
+                    }
+
+                    Ok(true)
+                }
+            };
+            let progress = match progress {
+                Ok(progress) => progress,
+                // too much for this technique, which leaves it to the next one (eg. a guess)
+                Err(Error::SolverLimit(message)) => {
+                    if config.verbose {
+                        println!("{:?} gave up: {}", technique, message);
+                    }
+                    false
+                }
+                Err(err) => return Err(err),
+            };
+
+            if progress {
+                step.technique = Some(technique);
+                break;
+            }
+        }
+
+        for pos in pre_board.diff(&game_board.grid)? {
+            match game_board.grid.get_cell(pos.0.into(), pos.1.into()) {
+                Some(Cell::Flag) => step.flagged.push(pos),
+                Some(_) => step.revealed.push(pos),
+                None => (),
+            }
+        }
+        step.explanations = game_board.take_explanations();
+        step.elapsed = start_time.elapsed();
+
+        if config.verbose {
+            for explanation in &step.explanations {
+                println!("{}", explanation);
+            }
+            println!("{}", game_board);
+            println!(
+                "progress: {} |  time taken: {}ms",
+                step.technique.is_some(),
+                step.elapsed.as_millis()
+            );
+        }
+
+        let progress = step.technique.is_some();
+        step_summary.push(step);
+
+        if hit_bomb {
+            return finish(GameResult::Loss, step_summary);
+        }
         if !progress {
-            step_summary.last_mut().unwrap().push("permute");
-            progress = game_board.permute_solve_step()?;
+            return finish(GameResult::Stuck, step_summary);
         }
 
-        println!("{}", game_board);
-        println!(
-            "progress: {} |  time taken: {}ms",
-            progress,
-            start_time.elapsed().as_millis()
+        solved = game_board.is_solved()?;
+    }
+
+    finish(GameResult::Win, step_summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        board::FirstClick,
+        grid::{Grid, Topology},
+    };
+    use std::str::FromStr;
+
+    fn quiet<O: RevealOracle>(name: &str) -> Box<dyn Strategy<O>> {
+        let mut strategy = strategy_by_name(name).unwrap();
+        strategy.configure("verbose", "false").unwrap();
+        strategy
+    }
+
+    #[test]
+    fn registry_1() {
+        let names: Vec<&str> = registry::<Grid<Cell>>()
+            .iter()
+            .map(|strategy| strategy.name())
+            .collect();
+        assert_eq!(
+            names,
+            vec!["basic", "pair", "linear", "permute", "sat", "guess"]
         );
 
-        solved = game_board.is_solved()?;
-        i += 1;
+        assert!(matches!(
+            strategy_by_name::<Grid<Cell>>("nope"),
+            Err(Error::Config(_))
+        ));
+    }
+
+    #[test]
+    fn configure_1() {
+        let mut strategy = strategy_by_name::<Grid<Cell>>("guess").unwrap();
+        assert!(strategy.configure("max_guesses", "2").is_ok());
+        assert!(matches!(
+            strategy.configure("max_guesses", "two"),
+            Err(Error::Config(_))
+        ));
+
+        // only the guessing strategy has a guess limit
+        let mut strategy = strategy_by_name::<Grid<Cell>>("permute").unwrap();
+        assert!(matches!(
+            strategy.configure("max_guesses", "2"),
+            Err(Error::Config(_))
+        ));
+    }
+
+    #[test]
+    fn run_1() {
+        // seed 14 needs a permute step, so the basic and pair strategies get stuck where the others
+        // win
+        let board =
+            GameBoard::new_first_click_seeded(16, 16, 40, (8, 8), FirstClick::Opening, 14).unwrap();
+
+        let basic = quiet("basic").run(board.clone(), (8, 8)).unwrap();
+        assert_eq!(basic.result, GameResult::Stuck);
+        assert!(
+            basic
+                .step_summary
+                .iter()
+                .all(|step| step.tried == [Technique::Basic])
+        );
+        assert_eq!(basic.step_summary.last().unwrap().technique, None);
+
+        let pair = quiet("pair").run(board.clone(), (8, 8)).unwrap();
+        assert_eq!(pair.result, GameResult::Stuck);
+
+        let permute = quiet("permute").run(board.clone(), (8, 8)).unwrap();
+        assert_eq!(permute.result, GameResult::Win);
+        let permute_step = permute
+            .step_summary
+            .iter()
+            .find(|step| step.technique == Some(Technique::Permute))
+            .unwrap();
+        assert_eq!(
+            permute_step.tried,
+            vec![
+                Technique::Basic,
+                Technique::Pair,
+                Technique::Linear,
+                Technique::Permute
+            ]
+        );
+        assert!(permute_step.frontier_size > 0);
+        assert!(!permute_step.valid_patterns.is_empty());
+
+        let guess = quiet("guess").run(board, (8, 8)).unwrap();
+        assert_eq!(guess.result, GameResult::Win);
+        let techniques = |result: &StrategyResult| -> Vec<Option<Technique>> {
+            result
+                .step_summary
+                .iter()
+                .map(|step| step.technique)
+                .collect()
+        };
+        assert_eq!(techniques(&guess), techniques(&permute));
+    }
+
+    #[test]
+    fn run_2() {
+        let board =
+            GameBoard::new_first_click_seeded(16, 16, 40, (8, 8), FirstClick::Opening, 5).unwrap();
 
-        // std::io::stdin().read_line(&mut String::new());
+        let mut strategy = quiet("permute");
+        strategy.configure("max_steps", "3").unwrap();
+        let result = strategy.run(board, (8, 8)).unwrap();
+        assert_eq!(result.result, GameResult::Stuck);
+        assert_eq!(result.step_summary.len(), 3);
     }
 
-    // println!("next starting cell...");
-    // std::io::stdin().read_line(&mut String::new());
-    if solved {
-        Ok(Some(step_summary))
-    } else {
-        Ok(None)
+    #[test]
+    fn run_3() {
+        // seed 5 only needs a 1-1 pattern, which the pair strategy finds without permuting
+        let board =
+            GameBoard::new_first_click_seeded(16, 16, 40, (8, 8), FirstClick::Opening, 5).unwrap();
+
+        let pair = quiet("pair").run(board, (8, 8)).unwrap();
+        assert_eq!(pair.result, GameResult::Win);
+        let pair_step = pair
+            .step_summary
+            .iter()
+            .find(|step| step.technique == Some(Technique::Pair))
+            .unwrap();
+        assert_eq!(pair_step.frontier_size, 4);
+        assert!(pair_step.valid_patterns.is_empty());
+    }
+
+    #[test]
+    fn run_4() {
+        // a ring of 1s with far too many patterns to permute, so the only way on is a guess
+        let mut solved = Grid::new(90, 3, Cell::Number(0)).with_topology(Topology::Cylinder);
+        for x in (0..90).step_by(3) {
+            solved.set_cell(x, 2, Cell::Bomb).unwrap();
+        }
+        let mut board = GameBoard::from(solved).unwrap();
+        for x in 0..90 {
+            assert!(board.reveal(x, 1).unwrap());
+        }
+
+        let guess = quiet("guess").run(board, (1, 1)).unwrap();
+        assert_eq!(
+            guess.step_summary[0].technique,
+            Some(Technique::Guess),
+            "{:?}",
+            guess.step_summary[0]
+        );
+        assert!(guess.step_summary[0].tried.contains(&Technique::Permute));
+    }
+
+    #[test]
+    fn pick_guess_1() {
+        // without a bomb count the guess falls back to estimates from the numbers
+        let board = GameBoard::from_partial_view(
+            Grid::from_str("?  ?  ?\n?  1  ?").unwrap(),
+            Grid::from_str("0  0  B\n0  1  0").unwrap(),
+        )
+        .unwrap();
+
+        let guess = pick_guess(&board).unwrap().unwrap();
+        assert!((guess.probability - 0.2).abs() < 1e-9, "{:?}", guess);
+    }
+
+    #[test]
+    fn step_summary_1() {
+        // seed 7 takes three guesses, all of which turn out safe
+        let board =
+            GameBoard::new_first_click_seeded(9, 9, 10, (4, 4), FirstClick::Opening, 7).unwrap();
+        let result = quiet("guess").run(board, (4, 4)).unwrap();
+        assert_eq!(result.result, GameResult::Win);
+
+        // every flag and revealed cell is recorded exactly once
+        let flagged: usize = result.step_summary.iter().map(|s| s.flagged.len()).sum();
+        let mut revealed: Vec<(u16, u16)> = result
+            .step_summary
+            .iter()
+            .flat_map(|step| step.revealed.clone())
+            .collect();
+        revealed.sort();
+        revealed.dedup();
+        assert_eq!(
+            revealed.len(),
+            result
+                .step_summary
+                .iter()
+                .map(|s| s.revealed.len())
+                .sum::<usize>()
+        );
+        assert!(flagged <= 10);
+
+        // a guess step records the guess alongside its technique, and nothing else does
+        let guesses: Vec<&Step> = result
+            .step_summary
+            .iter()
+            .filter(|step| step.guess.is_some())
+            .collect();
+        assert_eq!(guesses.len(), 3);
+        for step in &guesses {
+            assert_eq!(step.technique, Some(Technique::Guess));
+            assert!(step.revealed.contains(&step.guess.as_ref().unwrap().pos));
+        }
+        let probability = guesses[0].guess.as_ref().unwrap().probability;
+        assert!((probability - 1.0 / 3.0).abs() < 1e-9, "{}", probability);
+
+        let json = serde_json::to_value(&result).unwrap();
+        assert_eq!(
+            json["step_summary"][0]["tried"][0],
+            serde_json::json!("basic")
+        );
+        assert!(json["step_summary"][0]["elapsed"]["nanos"].is_number());
+    }
+
+    #[test]
+    fn step_summary_2() {
+        // seed 32 comes down to a coin flip, which loses
+        let board =
+            GameBoard::new_first_click_seeded(9, 9, 10, (4, 4), FirstClick::Opening, 32).unwrap();
+        let result = quiet("guess").run(board, (4, 4)).unwrap();
+        assert_eq!(result.result, GameResult::Loss);
+
+        let last = result.step_summary.last().unwrap();
+        assert_eq!(last.technique, Some(Technique::Guess));
+        let guess = last.guess.as_ref().unwrap();
+        assert!((guess.probability - 0.5).abs() < 1e-9, "{:?}", guess);
+        assert_eq!(
+            serde_json::to_value(&result).unwrap()["step_summary"][result.step_summary.len() - 1]["guess"]
+                ["probability"],
+            serde_json::json!(0.5)
+        );
     }
 }
@@ -1,10 +1,12 @@
 use std::fmt::Debug;
 
+use crate::error::Error;
+
 pub fn unflatten<T: Clone + Debug>(
     flat_vec: Vec<T>,
-    width: u8,
-    height: u8,
-) -> Result<Vec<Vec<T>>, String> {
+    width: u16,
+    height: u16,
+) -> Result<Vec<Vec<T>>, Error> {
     let mut out: Vec<Vec<T>> = vec![Vec::new()];
 
     for elem in flat_vec {
@@ -14,13 +16,19 @@ pub fn unflatten<T: Clone + Debug>(
             }
 
             // TODO: can i keep the unwrap here instead of the ok_or?
-            let row = out.last_mut().ok_or("error unwrapping row")?;
+            let row = out
+                .last_mut()
+                .ok_or(Error::InconsistentBoard("error unwrapping row".to_string()))?;
             row.push(elem)
         }
     }
 
     if out.len() != height as usize {
-        return Err("mismatch height".to_string());
+        return Err(Error::InconsistentBoard(format!(
+            "expected {} rows, found {}",
+            height,
+            out.len()
+        )));
     }
 
     Ok(out)